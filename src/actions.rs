@@ -0,0 +1,527 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::{gio, glib, Application, ApplicationWindow, ButtonsType, DialogFlags, MessageDialog, MessageType, ResponseType};
+
+use crate::logging::Level;
+use crate::routes::{Navigator, Route};
+use crate::universals::{self, Screens};
+use crate::{config, create, datepicker, diagnostics, drafts, export, globals, help, import, open, palette, recovery, routes, util, validation};
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// Everything the user can trigger. Keys are bound to actions through the keymap registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Back,
+    NewItinerary,
+    OpenItineraries,
+    Destinations,
+    Flights,
+    Places,
+    AddTestItinerary,
+    Import,
+    SaveItinerary,
+    SaveDestination,
+    SaveFlight,
+    SavePlace,
+    ConfirmImport,
+    ConfirmExport,
+    ReloadConfig,
+    Export,
+    Debug,
+    Help,
+    CommandPalette,
+    Undo,
+    Redo,
+    Drafts,
+    DiagnosticBundle,
+    PickDate,
+}
+
+/// Every action, in the order they are documented.
+pub const ALL: [Action; 25] = [
+    Action::Quit,
+    Action::Back,
+    Action::NewItinerary,
+    Action::OpenItineraries,
+    Action::Destinations,
+    Action::Flights,
+    Action::Places,
+    Action::AddTestItinerary,
+    Action::Import,
+    Action::SaveItinerary,
+    Action::SaveDestination,
+    Action::SaveFlight,
+    Action::SavePlace,
+    Action::ConfirmImport,
+    Action::ConfirmExport,
+    Action::ReloadConfig,
+    Action::Export,
+    Action::Debug,
+    Action::Help,
+    Action::CommandPalette,
+    Action::Undo,
+    Action::Redo,
+    Action::Drafts,
+    Action::DiagnosticBundle,
+    Action::PickDate,
+];
+
+impl Action {
+    /// Name used for the action in the `[keys]` table of the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Back => "back",
+            Action::NewItinerary => "new_itinerary",
+            Action::OpenItineraries => "open_itineraries",
+            Action::Destinations => "destinations",
+            Action::Flights => "flights",
+            Action::Places => "places",
+            Action::AddTestItinerary => "add_test_itinerary",
+            Action::Import => "import",
+            Action::SaveItinerary => "save_itinerary",
+            Action::SaveDestination => "save_destination",
+            Action::SaveFlight => "save_flight",
+            Action::SavePlace => "save_place",
+            Action::ConfirmImport => "confirm_import",
+            Action::ConfirmExport => "confirm_export",
+            Action::ReloadConfig => "reload_config",
+            Action::Export => "export",
+            Action::Debug => "debug",
+            Action::Help => "help",
+            Action::CommandPalette => "command_palette",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Drafts => "drafts",
+            Action::DiagnosticBundle => "diagnostic_bundle",
+            Action::PickDate => "pick_date",
+        }
+    }
+
+    /// Short description, phrased to follow "<key> to ...", e.g. "go back".
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Back => "go back",
+            Action::NewItinerary => "create new itinerary",
+            Action::OpenItineraries => "open itinerary",
+            Action::Destinations => "add destinations",
+            Action::Flights => "add flights",
+            Action::Places => "add places",
+            Action::AddTestItinerary => "add a test itinerary",
+            Action::Import => "import bookings",
+            Action::SaveItinerary => "create/save itinerary",
+            Action::SaveDestination => "save destination",
+            Action::SaveFlight => "add flight to itinerary",
+            Action::SavePlace => "add place to itinerary",
+            Action::ConfirmImport => "import",
+            Action::ConfirmExport => "export itinerary",
+            Action::ReloadConfig => "reload config",
+            Action::Export => "export itinerary",
+            Action::Debug => "open debug screen",
+            Action::Help => "show all shortcuts",
+            Action::CommandPalette => "search actions and itineraries",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Drafts => "switch drafts",
+            Action::DiagnosticBundle => "save a diagnostic bundle for bug reports",
+            Action::PickDate => "pick a date from a calendar",
+        }
+    }
+
+    /// Name of the window action buttons are bound to, e.g. "win.save_itinerary".
+    pub fn detailed_name(&self) -> String {
+        format!("win.{}", self.name())
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ALL.iter().cloned().find(|action| action.name() == name)
+    }
+}
+
+/// Registers every action on the window as "win.<name>", so buttons in the views run
+/// the same action as its key through `set_action_name`. Also registers "win.switch_draft",
+/// which takes the ID of the draft to switch to, and "win.open_itinerary", which takes a saved itinerary's title.
+pub fn install(app: &Application, window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    for action in ALL.iter().cloned() {
+        let window_action = gio::SimpleAction::new(action.name(), None);
+        window_action.connect_activate({
+            let app = app.clone();
+            let window = window.clone();
+            let navigator = navigator.clone();
+            move |_, _| perform(action, &app, &window, &navigator)
+        });
+        window.add_action(&window_action);
+    }
+
+    let switch = gio::SimpleAction::new(drafts::SWITCH_ACTION, Some(glib::VariantTy::UINT64));
+    switch.connect_activate({
+        let window = window.clone();
+        let navigator = navigator.clone();
+        move |_, parameter| {
+            if let Some(id) = parameter.and_then(|parameter| parameter.get::<u64>()) {
+                switch_draft(&window, &navigator, id);
+            }
+        }
+    });
+    window.add_action(&switch);
+
+    let open = gio::SimpleAction::new(open::OPEN_ACTION, Some(glib::VariantTy::STRING));
+    open.connect_activate({
+        let window = window.clone();
+        let navigator = navigator.clone();
+        move |_, parameter| {
+            if let Some(title) = parameter.and_then(|parameter| parameter.get::<String>()) {
+                open_itinerary(&window, &navigator, title);
+            }
+        }
+    });
+    window.add_action(&open);
+}
+
+/// Opens a saved itinerary on the CREATE screen.
+pub fn open_itinerary(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>, title: String) {
+    let route = Route::Create { itinerary: Some(title) };
+    if navigator.borrow().screen() == Screens::Open {
+        routes::route_forward(window, navigator, route);
+    } else {
+        routes::route_jump(window, navigator, route);
+    }
+}
+
+/// Makes the draft with the given ID the one being edited and opens it on the CREATE screen.
+pub fn switch_draft(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>, id: u64) {
+    let switched = {
+        let database = globals::get_database();
+        let mut database_instance = database.lock().unwrap();
+        database_instance.switch_draft(id)
+    };
+    if !switched {
+        log(Level::Error, &format!("No draft with ID {} to switch to.", id));
+        return;
+    }
+
+    if navigator.borrow().screen() == Screens::Drafts {
+        routes::route_forward(window, navigator, Route::Create { itinerary: None });
+    } else {
+        routes::route_jump(window, navigator, Route::Create { itinerary: None });
+    }
+}
+
+/// Reloads the config file and applies the window settings without restarting.
+/// An invalid file is reported and the current settings are kept.
+fn reload_config(window: &ApplicationWindow) {
+    let config = match config::load() {
+        Ok(config) => config,
+        Err(message) => {
+            log(Level::Error, &format!("Config not reloaded: {}", message));
+            return;
+        }
+    };
+
+    let previous = globals::get_config();
+    if config.database != previous.database {
+        log(Level::Warn, "The database path change takes effect after a restart.");
+    }
+
+    window.set_title(&config.window_title);
+    window.resize(config.window_width, config.window_height);
+    let style_context = window.style_context();
+    for (_, class) in config::THEMES.iter() {
+        style_context.remove_class(class);
+    }
+    style_context.add_class(config.theme_class());
+
+    globals::set_config(config);
+    log(Level::Info, "Config reloaded.");
+}
+
+/// Saves the itinerary being edited. Failures are logged and shown in a banner on the current screen.
+/// Fields that fail the CREATE screen's rules block the save, even when saved from another screen.
+fn save_itinerary(window: &ApplicationWindow) -> bool {
+    let result = {
+        let database = globals::get_database();
+        let mut database_instance = database.lock().unwrap();
+        create::check(&database_instance.session_snapshot()).map(|()| database_instance.store_session_storage_in_database())
+    };
+    let result = match result {
+        Ok(result) => result,
+        Err(message) => {
+            log(Level::Warn, &format!("Itinerary not saved: {}", message));
+            universals::show_error(window, &format!("The itinerary could not be saved. {}", message), &message);
+            return false;
+        }
+    };
+
+    let details = match result {
+        Ok(s) if s == "SUCCESS" => {
+            log(Level::Info, "Itinerary saved to the database.");
+            return true;
+        }
+        Ok(s) => s,
+        Err(e) => e.to_string(),
+    };
+    log(Level::Error, &format!("Itinerary not saved: {}", details));
+    universals::show_error(window, "The itinerary could not be saved.", &details);
+    false
+}
+
+/// Saves every draft with unsaved changes. A draft that can't be saved is left as the one being edited,
+/// so the current screen is redrawn with it and the failure is shown in a banner.
+fn save_all_drafts(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) -> bool {
+    let result = {
+        let database = globals::get_database();
+        let mut database_instance = database.lock().unwrap();
+        database_instance.save_all_drafts(create::check)
+    };
+
+    match result {
+        Ok(()) => {
+            log(Level::Info, "All drafts saved to the database.");
+            true
+        }
+        Err((title, reason)) => {
+            log(Level::Error, &format!("Draft '{}' not saved: {}", title, reason));
+            routes::refresh(window, navigator);
+            // Marks the invalid fields when the draft is on screen
+            if navigator.borrow().screen() == Screens::Create {
+                validation::validate_current_form();
+            }
+            universals::show_error(window, &format!("The draft '{}' could not be saved.", title), &reason);
+            false
+        }
+    }
+}
+
+/// Runs `proceed` right away if there are no unsaved changes. Otherwise asks whether to save them,
+/// discard them or stay, and runs `proceed` after saving or discarding.
+/// `leaving` completes the question, e.g. "quitting" asks "Save changes before quitting?",
+/// and `note` says what becomes of discarded changes.
+/// With `all_drafts` every draft is asked about, otherwise only the one being edited.
+fn confirm_unsaved<F: Fn() + 'static>(
+    window: &ApplicationWindow,
+    navigator: &Rc<RefCell<Navigator>>,
+    leaving: &str,
+    note: &str,
+    all_drafts: bool,
+    proceed: F,
+) {
+    // Titles of the drafts with unsaved changes
+    let unsaved: Vec<String> = {
+        let database = globals::get_database();
+        let database_instance = database.lock().unwrap();
+        database_instance
+            .list_drafts()
+            .into_iter()
+            .filter(|draft| draft.unsaved && (all_drafts || draft.active))
+            .map(|draft| draft.title)
+            .collect()
+    };
+    if unsaved.is_empty() {
+        proceed();
+        return;
+    }
+
+    let message = match unsaved.as_slice() {
+        [title] if title == "Untitled" => format!("Save the new itinerary before {}?", leaving),
+        [title] => format!("Save changes to '{}' before {}?", title, leaving),
+        titles => format!("Save changes to {} drafts ({}) before {}?", titles.len(), titles.join(", "), leaving),
+    };
+    let dialog = MessageDialog::new(
+        Some(window),
+        DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+        MessageType::Warning,
+        ButtonsType::None,
+        &message,
+    );
+    dialog.set_secondary_text(Some(note));
+    dialog.add_button("Cancel", ResponseType::Cancel);
+    dialog.add_button("Discard", ResponseType::Reject);
+    dialog.add_button("Save", ResponseType::Accept);
+    dialog.set_default_response(ResponseType::Accept);
+
+    dialog.connect_response({
+        let window = window.clone();
+        let navigator = navigator.clone();
+        move |dialog, response| {
+            dialog.close();
+            match response {
+                ResponseType::Accept => {
+                    // Marks the invalid fields when the draft is on screen
+                    if navigator.borrow().screen() == Screens::Create && !validation::validate_current_form() {
+                        return;
+                    }
+                    let saved = if all_drafts { save_all_drafts(&window, &navigator) } else { save_itinerary(&window) };
+                    // Otherwise the user stays put with the error shown, to fix it and try again
+                    if saved {
+                        proceed();
+                    }
+                }
+                ResponseType::Reject => {
+                    {
+                        let database = globals::get_database();
+                        let mut database_instance = database.lock().unwrap();
+                        if all_drafts {
+                            database_instance.discard_all_drafts();
+                        } else {
+                            database_instance.discard_session_storage();
+                        }
+                    }
+                    log(Level::Info, "Unsaved changes discarded.");
+                    proceed();
+                }
+                // Cancel, or the dialog was closed
+                _ => {}
+            }
+        }
+    });
+
+    dialog.show_all();
+}
+
+/// Runs an action. Screen changes go through the router, so actions not valid from the
+/// current screen do nothing.
+pub fn perform(action: Action, app: &Application, window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    match action {
+        Action::Quit => {
+            let quit = {
+                let app = app.clone();
+                move || {
+                    // Keep whatever is still unsaved for recovery on the next launch
+                    recovery::autosave();
+                    app.quit();
+                }
+            };
+            confirm_unsaved(window, navigator, "quitting", "Changes you discard are lost for good.", true, quit);
+        }
+        Action::Back if navigator.borrow().screen() == Screens::Create => {
+            let back = {
+                let window = window.clone();
+                let navigator = navigator.clone();
+                move || routes::route_back(&window, &navigator)
+            };
+            let note = "Changes you discard can still be brought back with undo until the app is closed.";
+            confirm_unsaved(window, navigator, "leaving", note, false, back);
+        }
+        Action::Back => routes::route_back(window, navigator),
+        Action::NewItinerary => {
+            // The itinerary being edited is kept as a draft to switch back to
+            {
+                let database = globals::get_database();
+                let mut database_instance = database.lock().unwrap();
+                database_instance.new_draft();
+            }
+            routes::route_forward(window, navigator, Route::Create { itinerary: None });
+        }
+        Action::OpenItineraries => routes::route_forward(window, navigator, Route::Open),
+        Action::Destinations => routes::route_forward(window, navigator, Route::Destinations),
+        Action::Flights => routes::route_forward(window, navigator, Route::Flights),
+        Action::Places => routes::route_forward(window, navigator, Route::Places),
+        Action::AddTestItinerary => {
+            // Test function: Adds a sample itinerary entry
+            let database = globals::get_database();
+            let database_instance = database.lock().unwrap();
+
+            log(Level::Debug, "Adding test itinerary with title: Yami");
+            match database_instance.add_itinerary("Yami".to_string()) {
+                Err(e) => log(Level::Error, &format!(
+                    "Error adding itinerary in database: {}",
+                    e
+                )),
+                Ok(s) => log(Level::Debug, &format!("Itinerary added: {}", s)),
+            }
+        }
+        Action::Import => routes::route_forward(window, navigator, Route::Import),
+        Action::SaveItinerary => {
+            // Invalid fields are pointed out on the screen instead
+            if !validation::validate_current_form() {
+                return;
+            }
+            if save_itinerary(window) {
+                routes::route_back(window, navigator);
+            }
+        }
+        Action::SaveDestination => {
+            if !validation::validate_current_form() {
+                return;
+            }
+            {
+                let database = globals::get_database();
+                let mut database_instance = database.lock().unwrap();
+                database_instance.add_current_destination_to_session_storage();
+            }
+            routes::route_back(window, navigator);
+        }
+        Action::SaveFlight | Action::SavePlace => {
+            if !validation::validate_current_form() {
+                return;
+            }
+            let (what, result) = {
+                let database = globals::get_database();
+                let mut database_instance = database.lock().unwrap();
+                if action == Action::SaveFlight {
+                    ("flight", database_instance.add_flight_from_session_storage())
+                } else {
+                    ("place", database_instance.add_place_from_session_storage())
+                }
+            };
+
+            let details = match result {
+                Ok(s) if s == "SUCCESS" => {
+                    log(Level::Info, &format!("{} added to the itinerary.", util::capitalize(what)));
+                    routes::route_back(window, navigator);
+                    return;
+                }
+                Ok(s) => s,
+                Err(e) => e.to_string(),
+            };
+            log(Level::Error, &format!("{} not added: {}", util::capitalize(what), details));
+            universals::show_error(window, &format!("The {} could not be added to the saved itinerary.", what), &details);
+        }
+        Action::ConfirmImport => import::confirm(window, navigator),
+        Action::ConfirmExport => export::confirm(window, navigator),
+        Action::ReloadConfig => reload_config(window),
+        Action::Export => {
+            // From CREATE the itinerary being edited is preselected
+            let itinerary = if navigator.borrow().screen() == Screens::Create {
+                let database = globals::get_database();
+                let database_instance = database.lock().unwrap();
+                database_instance.get_session_storage().get("title").filter(|title| !title.is_empty()).cloned()
+            } else {
+                None
+            };
+            routes::route_forward(window, navigator, Route::Export { itinerary });
+        }
+        Action::Debug => routes::route_forward(window, navigator, Route::Debug),
+        Action::Drafts => routes::route_forward(window, navigator, Route::Drafts),
+        Action::DiagnosticBundle => diagnostics::save(window, navigator),
+        Action::PickDate => datepicker::open_for_focus(window),
+        Action::Help => help::show(window, navigator.borrow().screen()),
+        Action::CommandPalette => palette::show(app, window, navigator),
+        Action::Undo | Action::Redo => {
+            let result = {
+                let database = globals::get_database();
+                let mut database_instance = database.lock().unwrap();
+                if action == Action::Undo {
+                    database_instance.undo()
+                } else {
+                    database_instance.redo()
+                }
+            };
+
+            match result {
+                // Redraw the screen so it shows the restored values
+                Ok(true) => routes::refresh(window, navigator),
+                Ok(false) => log(Level::Info, &format!("Nothing to {}.", action.name())),
+                Err(e) => log(Level::Error, &format!("Could not {}: {}", action.name(), e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::database::Database;
+use crate::globals;
+use crate::logging::Level;
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// File extension used for itinerary bundles.
+pub const EXTENSION: &str = "itinerary";
+
+/// Identifies bundle files and the layout version they were written with.
+const BUNDLE_FORMAT: &str = "itinerary-planner-bundle";
+const BUNDLE_VERSION: u32 = 1;
+
+/// A file attached to an itinerary, carried inside the bundle.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Attachment {
+    pub name: String,
+    pub data: String,
+}
+
+/// A single itinerary with its attachments, as written to a bundle file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Bundle {
+    pub format: String,
+    pub version: u32,
+    pub title: String,
+    pub itinerary: Value,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+}
+
+/// What to do when an imported itinerary has the same ID or title as an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    Skip,
+    Overwrite,
+    Rename,
+}
+
+impl ConflictPolicy {
+    /// Reads a policy typed by the user. Anything unrecognised falls back to skipping.
+    pub fn parse(text: &str) -> ConflictPolicy {
+        match text.trim().to_lowercase().as_str() {
+            "overwrite" | "o" => ConflictPolicy::Overwrite,
+            "rename" | "r" => ConflictPolicy::Rename,
+            _ => ConflictPolicy::Skip,
+        }
+    }
+}
+
+/// Describes how a bundle would merge into the database.
+#[derive(Debug, Clone)]
+pub struct MergePreview {
+    pub title: String,
+    pub attachment_count: usize,
+    /// Title of the existing itinerary with the same ID or title, if any.
+    pub conflict: Option<String>,
+}
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes bytes as standard base64 so attachments can be stored in JSON.
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+
+        out.push(BASE64_ALPHABET[(n >> 18) as usize & 63] as char);
+        out.push(BASE64_ALPHABET[(n >> 12) as usize & 63] as char);
+        out.push(if chunk.len() > 1 { BASE64_ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        out.push(if chunk.len() > 2 { BASE64_ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+
+    out
+}
+
+/// Decodes standard base64, failing on characters outside the alphabet.
+fn decode_base64(text: &str) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for c in text.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let value = match BASE64_ALPHABET.iter().position(|a| *a == c) {
+            Some(value) => value as u32,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid base64 in bundle attachment")),
+        };
+
+        buffer = (buffer << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Splits the comma separated "attachments" field of an itinerary into file paths.
+fn attachment_paths(itinerary: &Value) -> Vec<String> {
+    itinerary
+        .get("attachments")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .split(',')
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// Writes one itinerary and its attachments to a bundle file at `path`.
+pub fn export(database: &Database, title: &str, path: &Path) -> Result<String, io::Error> {
+    let itinerary = match database.get_itinerary(title) {
+        Some(itinerary) => itinerary,
+        None => return Ok("ERROR_NO_ITINERARY".to_string()),
+    };
+
+    let mut attachments = Vec::new();
+    for attachment_path in attachment_paths(&itinerary) {
+        match fs::read(&attachment_path) {
+            Ok(bytes) => {
+                let name = Path::new(&attachment_path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| attachment_path.clone());
+                attachments.push(Attachment { name, data: encode_base64(&bytes) });
+            }
+            Err(e) => log(Level::Error, &format!("Could not read attachment {}: {}", attachment_path, e)),
+        }
+    }
+
+    let bundle = Bundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        title: title.to_string(),
+        itinerary,
+        attachments,
+    };
+
+    fs::write(path, serde_json::to_string_pretty(&bundle)?)?;
+    Ok("SUCCESS".to_string())
+}
+
+/// Reads and checks a bundle file.
+pub fn read(path: &str) -> io::Result<Bundle> {
+    let content = fs::read_to_string(path)?;
+    let bundle: Bundle = serde_json::from_str(&content)?;
+
+    if bundle.format != BUNDLE_FORMAT || bundle.version > BUNDLE_VERSION || !bundle.itinerary.is_object() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("'{}' is not a supported itinerary bundle", path),
+        ));
+    }
+
+    Ok(bundle)
+}
+
+/// Finds an existing itinerary that conflicts with the bundle, matching by ID first, then by title.
+/// Itineraries saved before they had IDs are only matched by title.
+fn find_conflict(database: &Database, bundle: &Bundle) -> Option<String> {
+    bundle
+        .itinerary
+        .get("id")
+        .and_then(|v| v.as_i64())
+        .and_then(|id| database.itinerary_title_by_id(id))
+        .or_else(|| database.get_itinerary(&bundle.title).map(|_| bundle.title.clone()))
+}
+
+/// Describes what importing the bundle at `path` would do, without changing anything.
+pub fn preview(database: &Database, path: &str) -> io::Result<MergePreview> {
+    let bundle = read(path)?;
+
+    Ok(MergePreview {
+        title: bundle.title.clone(),
+        attachment_count: bundle.attachments.len(),
+        conflict: find_conflict(database, &bundle),
+    })
+}
+
+/// Picks the first free title of the form "Title (2)", "Title (3)", ...
+fn free_title(database: &Database, title: &str) -> String {
+    let mut n = 2;
+    loop {
+        let candidate = format!("{} ({})", title, n);
+        if database.get_itinerary(&candidate).is_none() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+/// Returns `name`, or "name (2).ext", "name (3).ext", ... if it is already in `used`, and adds it there.
+/// Attachments from different folders may share a file name.
+fn unique_name(name: &str, used: &mut HashSet<String>) -> String {
+    let path = Path::new(name);
+    let stem = path.file_stem().map_or(name.to_string(), |stem| stem.to_string_lossy().to_string());
+    let extension = path.extension().map(|extension| format!(".{}", extension.to_string_lossy())).unwrap_or_default();
+
+    let mut candidate = name.to_string();
+    let mut n = 2;
+    while used.contains(&candidate) {
+        candidate = format!("{} ({}){}", stem, n, extension);
+        n += 1;
+    }
+    used.insert(candidate.clone());
+    candidate
+}
+
+/// Extracts attachments next to the database file and returns their new paths.
+fn unpack_attachments(database: &Database, title: &str, attachments: &[Attachment]) -> io::Result<Vec<String>> {
+    let database_path = PathBuf::from(database.get_path());
+    let base = database_path.parent().unwrap_or_else(|| Path::new("."));
+    let folder = base.join("attachments").join(crate::export::file_stem(title));
+    fs::create_dir_all(&folder)?;
+
+    let mut paths = Vec::new();
+    let mut used = HashSet::new();
+    for attachment in attachments {
+        // Only the file name is kept so a bundle can't write outside the attachments folder
+        let name = Path::new(&attachment.name)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "attachment".to_string());
+        let path = folder.join(unique_name(&name, &mut used));
+        fs::write(&path, decode_base64(&attachment.data)?)?;
+        paths.push(path.to_string_lossy().to_string());
+    }
+
+    Ok(paths)
+}
+
+/// Merges the bundle at `path` into the database, resolving conflicts with `policy`.
+/// An overwrite is undone together with the import.
+pub fn import(database: &Database, path: &str, policy: ConflictPolicy) -> Result<String, io::Error> {
+    globals::get_history().begin_group();
+    let result = merge(database, path, policy);
+    globals::get_history().end_group();
+    result
+}
+
+fn merge(database: &Database, path: &str, policy: ConflictPolicy) -> Result<String, io::Error> {
+    let bundle = read(path)?;
+    let mut itinerary = bundle.itinerary.clone();
+    let mut title = bundle.title.clone();
+    let mut replaced = None;
+
+    if let Some(existing) = find_conflict(database, &bundle) {
+        match policy {
+            ConflictPolicy::Skip => {
+                log(Level::Warn, &format!("Skipped '{}', it conflicts with '{}'", bundle.title, existing));
+                return Ok("SKIPPED_CONFLICT".to_string());
+            }
+            ConflictPolicy::Overwrite => {
+                // Matched by ID under another title, so the bundle's title may belong to a third itinerary
+                if existing != title && database.get_itinerary(&title).is_some() {
+                    title = free_title(database, &title);
+                }
+                log(Level::Warn, &format!("Overwriting '{}' with '{}'", existing, title));
+                replaced = Some(existing);
+            }
+            ConflictPolicy::Rename => {
+                if database.get_itinerary(&title).is_some() {
+                    title = free_title(database, &title);
+                }
+                // The copy is a separate itinerary, so it must not share the original's ID
+                itinerary["id"] = json!(database.new_itinerary_id());
+                log(Level::Info, &format!("Importing '{}' as '{}'", bundle.title, title));
+            }
+        }
+    }
+
+    if !bundle.attachments.is_empty() {
+        let paths = unpack_attachments(database, &title, &bundle.attachments)?;
+        itinerary["attachments"] = json!(paths.join(", "));
+    }
+
+    // Only replaced once the attachments are unpacked, so a failed import leaves the itinerary as it was
+    match replaced {
+        Some(existing) => database.replace_itinerary(&existing, &title, itinerary)?,
+        None => database.put_itinerary(&title, itinerary)?,
+    }
+    log(Level::Info, &format!("Imported itinerary '{}' from {}", title, path));
+    Ok("SUCCESS".to_string())
+}
@@ -0,0 +1,271 @@
+use crate::database::{BookingKind, Database};
+use crate::options::OPTIONS_USAGE;
+use crate::{export, globals, printable, util};
+
+/// Subcommands that run headless, without starting GTK.
+pub const COMMANDS: [&str; 7] = ["list", "show", "create", "add-destination", "export", "validate", "help"];
+
+const USAGE: &str = "Usage: itinerary_planner [OPTIONS] [COMMAND]
+
+Without a command the GTK app is started.
+
+Commands:
+  list                                          List saved itineraries
+  show <itinerary>                              Print an itinerary day by day
+  create <title> --start DATE --end DATE        Create an itinerary
+  add-destination <itinerary> <country> <start> <end>
+                                                Add a destination to an itinerary
+  export <itinerary> [--format FORMATS] [--folder DIR] [--redact]
+                                                Export an itinerary (default format: all)
+  validate [<itinerary>]                        Check itineraries for problems
+  help                                          Show this message
+
+Options:
+  --verbose                                     Print the debug log after the command";
+
+/// Whether the command line asks for a headless subcommand.
+pub fn is_command(args: &[String]) -> bool {
+    args.get(1).map_or(false, |arg| COMMANDS.contains(&arg.as_str()))
+}
+
+/// Returns the value following `--name`, if present.
+fn option(args: &[String], name: &str) -> Option<String> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|index| args.get(index + 1))
+        .cloned()
+}
+
+/// Returns the positional arguments after the subcommand, skipping options and their values.
+fn positional(args: &[String]) -> Vec<String> {
+    let flags = ["--redact", "--verbose"];
+    let mut values = Vec::new();
+    let mut iter = args.iter().skip(2);
+
+    while let Some(arg) = iter.next() {
+        if flags.contains(&arg.as_str()) {
+            continue;
+        }
+        if arg.starts_with("--") {
+            iter.next();
+            continue;
+        }
+        values.push(arg.clone());
+    }
+
+    values
+}
+
+/// Checks one itinerary and lists the problems found.
+pub fn validate_itinerary(database: &Database, title: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    let itinerary = match database.get_itinerary(title) {
+        Some(itinerary) => itinerary,
+        None => return vec![format!("'{}' does not exist", title)],
+    };
+
+    if !itinerary.is_object() {
+        return vec![format!("'{}' is not an object", title)];
+    }
+
+    let field = |key: &str| itinerary.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let start = util::parse_date(&field("start_date"));
+    let end = util::parse_date(&field("end_date"));
+
+    for (key, date) in [("start_date", start), ("end_date", end)].iter() {
+        if !field(*key).is_empty() && date.is_none() {
+            problems.push(format!("{} '{}' is not a valid date", key, field(*key)));
+        }
+    }
+    if let (Some(start), Some(end)) = (start, end) {
+        if start > end {
+            problems.push("start_date is after end_date".to_string());
+        }
+    }
+
+    for destination in database.list_destinations(title) {
+        match (util::parse_date(&destination.start_date), util::parse_date(&destination.end_date)) {
+            (Some(d_start), Some(d_end)) => {
+                if d_start > d_end {
+                    problems.push(format!("destination '{}' starts after it ends", destination.title));
+                }
+                if start.map_or(false, |start| d_start < start) || end.map_or(false, |end| d_end > end) {
+                    problems.push(format!("destination '{}' is outside the itinerary dates", destination.title));
+                }
+            }
+            _ => problems.push(format!("destination '{}' has invalid dates", destination.title)),
+        }
+    }
+
+    for kind in [BookingKind::Flight, BookingKind::Travel, BookingKind::Accommodation].iter() {
+        let stored = itinerary.get(kind.key()).and_then(|v| v.as_array()).map_or(0, |list| list.len());
+        let bookings = database.list_bookings(title, *kind);
+        if bookings.len() != stored {
+            problems.push(format!("{} list could not be read", kind.key()));
+        }
+        for booking in bookings {
+            if booking.title.is_empty() {
+                problems.push(format!("a {} booking has no title", kind.label().to_lowercase()));
+            }
+        }
+    }
+
+    let stored_expenses = itinerary.get("expenses").and_then(|v| v.as_array()).map_or(0, |list| list.len());
+    if database.list_expenses(title).len() != stored_expenses {
+        problems.push("expenses list could not be read".to_string());
+    }
+
+    let stored_places = itinerary.get("places").and_then(|v| v.as_array()).map_or(0, |list| list.len());
+    if database.list_places(title).len() != stored_places {
+        problems.push("places list could not be read".to_string());
+    }
+
+    problems
+}
+
+/// Runs a headless subcommand and returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let database = globals::get_database();
+    let mut db_instance = database.lock().unwrap();
+    let command = args.get(1).map(|s| s.as_str()).unwrap_or("help");
+    let values = positional(args);
+
+    let code = match command {
+        "list" => {
+            for title in db_instance.list_all_itineraries() {
+                println!("{}", title);
+            }
+            0
+        }
+        "show" => match values.get(0).and_then(|title| printable::build_plan(&db_instance, title)) {
+            Some(plan) => {
+                print!("{}", printable::to_markdown(&plan, false));
+                0
+            }
+            None => {
+                eprintln!("No itinerary named '{}'", values.get(0).map(|s| s.as_str()).unwrap_or(""));
+                1
+            }
+        },
+        "create" => match (values.get(0), option(args, "--start"), option(args, "--end")) {
+            (Some(title), ..) if db_instance.get_itinerary(title).is_some() => {
+                eprintln!("An itinerary named '{}' already exists", title);
+                1
+            }
+            (Some(_), Some(start), Some(end)) if util::parse_date(&start).is_none() || util::parse_date(&end).is_none() => {
+                let date = if util::parse_date(&start).is_none() { start } else { end };
+                eprintln!("'{}' is not a valid date", date);
+                1
+            }
+            (Some(title), Some(start), Some(end)) => {
+                db_instance.set_kv_session_storage("title", title);
+                db_instance.set_kv_session_storage("start_date", &start);
+                db_instance.set_kv_session_storage("end_date", &end);
+                match db_instance.store_session_storage_in_database() {
+                    Ok(status) if status == "SUCCESS" => {
+                        println!("Created '{}'", title);
+                        0
+                    }
+                    Ok(status) => {
+                        eprintln!("Could not create '{}': {}", title, status);
+                        1
+                    }
+                    Err(e) => {
+                        eprintln!("Could not create '{}': {}", title, e);
+                        1
+                    }
+                }
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "add-destination" => {
+            if values.len() != 4 {
+                eprintln!("{}", USAGE);
+                2
+            } else if db_instance.get_itinerary(&values[0]).is_none() {
+                eprintln!("No itinerary named '{}'", values[0]);
+                1
+            } else {
+                db_instance.set_kv_session_storage("title", &values[0]);
+                db_instance.set_kv_session_storage("current_country_title", &values[1]);
+                db_instance.set_kv_session_storage("current_country_start_date", &values[2]);
+                db_instance.set_kv_session_storage("current_country_end_date", &values[3]);
+                db_instance.add_current_destination_to_session_storage();
+                match db_instance.store_session_storage_in_database() {
+                    Ok(status) if status == "SUCCESS" => {
+                        println!("Added '{}' to '{}'", values[1], values[0]);
+                        0
+                    }
+                    Ok(status) => {
+                        eprintln!("Could not add destination: {}", status);
+                        1
+                    }
+                    Err(e) => {
+                        eprintln!("Could not add destination: {}", e);
+                        1
+                    }
+                }
+            }
+        }
+        "export" => match values.get(0) {
+            Some(title) => {
+                let formats = option(args, "--format").unwrap_or_else(|| "all".to_string());
+                let folder = option(args, "--folder").unwrap_or_else(|| ".".to_string());
+                let redact = args.iter().any(|arg| arg == "--redact");
+                match export::write(&db_instance, title, &folder, &formats, redact) {
+                    Ok(status) if status == "SUCCESS" => {
+                        println!("Exported '{}' to {}", title, folder);
+                        0
+                    }
+                    Ok(status) => {
+                        eprintln!("Could not export '{}': {}", title, status);
+                        1
+                    }
+                    Err(e) => {
+                        eprintln!("Could not export '{}': {}", title, e);
+                        1
+                    }
+                }
+            }
+            None => {
+                eprintln!("{}", USAGE);
+                2
+            }
+        },
+        "validate" => {
+            let titles = match values.get(0) {
+                Some(title) => vec![title.clone()],
+                None => db_instance.list_all_itineraries(),
+            };
+
+            let mut failed = false;
+            for title in titles {
+                let problems = validate_itinerary(&db_instance, &title);
+                if problems.is_empty() {
+                    println!("{}: OK", title);
+                } else {
+                    failed = true;
+                    for problem in problems {
+                        println!("{}: {}", title, problem);
+                    }
+                }
+            }
+            if failed { 1 } else { 0 }
+        }
+        _ => {
+            println!("{}\n\nApp options:\n{}", USAGE, OPTIONS_USAGE);
+            0
+        }
+    };
+
+    if args.iter().any(|arg| arg == "--verbose") {
+        for line in globals::get_debug_log() {
+            eprintln!("{}", line);
+        }
+    }
+
+    code
+}
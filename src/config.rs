@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+use crate::keymap::Keymap;
+use crate::paths;
+
+/// Themes the app ships, mapped to the style class set on the main window.
+pub const THEMES: [(&str, &str); 2] = [("dark", "black-window"), ("light", "light-window")];
+
+/// User settings read from `config.toml` in the XDG config directory.
+/// Every setting is optional in the file; missing ones keep their default.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub window_width: i32,
+    pub window_height: i32,
+    pub window_title: String,
+    /// chrono format used to write dates and tried first when reading them, e.g. "%d/%m/%Y".
+    pub date_format: String,
+    /// Database file. The --database command-line option takes precedence.
+    pub database: Option<String>,
+    pub theme: String,
+    /// Key overrides by action name, e.g. `save_itinerary = "Ctrl+S"`. Unlisted actions keep their default key.
+    pub keys: HashMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            window_width: 1300,
+            window_height: 800,
+            window_title: "Black Window".to_string(),
+            date_format: "%Y-%m-%d".to_string(),
+            database: None,
+            theme: "dark".to_string(),
+            keys: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Returns the window style class for the configured theme.
+    pub fn theme_class(&self) -> &'static str {
+        THEMES
+            .iter()
+            .find(|(name, _)| *name == self.theme)
+            .map(|(_, class)| *class)
+            .unwrap_or("black-window")
+    }
+
+    /// Checks the settings, returning one message per problem.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if !(320..=10000).contains(&self.window_width) {
+            problems.push(format!("window_width must be between 320 and 10000 (got {})", self.window_width));
+        }
+        if !(240..=10000).contains(&self.window_height) {
+            problems.push(format!("window_height must be between 240 and 10000 (got {})", self.window_height));
+        }
+        if self.window_title.trim().is_empty() {
+            problems.push("window_title must not be empty".to_string());
+        }
+
+        let items: Vec<Item> = StrftimeItems::new(&self.date_format).collect();
+        if items.iter().any(|item| matches!(item, Item::Error)) {
+            problems.push(format!("date_format '{}' is not a valid chrono format", self.date_format));
+        } else {
+            // Dates are written and read back with the format, so it has to round-trip a full date
+            let known = NaiveDate::from_ymd_opt(2025, 3, 28).expect("known date is valid");
+            let written = known.format(&self.date_format).to_string();
+            if NaiveDate::parse_from_str(&written, &self.date_format) != Ok(known) {
+                problems.push(format!(
+                    "date_format '{}' must contain a day, month and year that can be read back, e.g. %d/%m/%Y",
+                    self.date_format
+                ));
+            }
+        }
+
+        if let Some(database) = &self.database {
+            if database.trim().is_empty() {
+                problems.push("database must not be empty when set".to_string());
+            }
+        }
+
+        if !THEMES.iter().any(|(name, _)| *name == self.theme) {
+            let names: Vec<&str> = THEMES.iter().map(|(name, _)| *name).collect();
+            problems.push(format!("theme must be one of {} (got '{}')", names.join(", "), self.theme));
+        }
+
+        if let Err(key_problems) = Keymap::with_overrides(&self.keys) {
+            problems.extend(key_problems);
+        }
+
+        problems
+    }
+}
+
+/// Location of the config file.
+pub fn path() -> PathBuf {
+    paths::config_dir().join("config.toml")
+}
+
+/// Loads and validates the config file. A missing file gives the defaults.
+/// Errors name the file and, for syntax errors, the line at fault.
+pub fn load() -> Result<Config, String> {
+    let path = path();
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    let config: Config = toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let problems = config.validate();
+    if !problems.is_empty() {
+        return Err(format!("{}: {}", path.display(), problems.join("; ")));
+    }
+
+    Ok(config)
+}
@@ -0,0 +1,524 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::database::{Booking, BookingKind, Database, Expense};
+use crate::logging::Level;
+use crate::validation::Rule;
+use crate::{globals, util};
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// The kinds of CSV files an itinerary is exported to, one file per kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvKind {
+    Bookings(BookingKind),
+    Expenses,
+}
+
+/// Every CSV kind, in the order the files are written.
+pub const KINDS: [CsvKind; 4] = [
+    CsvKind::Bookings(BookingKind::Flight),
+    CsvKind::Bookings(BookingKind::Travel),
+    CsvKind::Bookings(BookingKind::Accommodation),
+    CsvKind::Expenses,
+];
+
+impl CsvKind {
+    /// Column headers. These are part of the file format, so existing names must not change.
+    pub fn headers(&self) -> &'static [&'static str] {
+        match self {
+            CsvKind::Bookings(BookingKind::Flight) => &["uid", "flight", "departure", "arrival", "route", "confirmation", "notes"],
+            CsvKind::Bookings(BookingKind::Travel) => &["uid", "description", "departure", "arrival", "route", "confirmation", "notes"],
+            CsvKind::Bookings(BookingKind::Accommodation) => &["uid", "name", "check_in", "check_out", "address", "confirmation", "notes"],
+            CsvKind::Expenses => &["date", "description", "category", "amount", "currency"],
+        }
+    }
+
+    /// Suffix of the exported file name, e.g. "japan-2025-flights.csv".
+    pub fn file_suffix(&self) -> &'static str {
+        match self {
+            CsvKind::Bookings(kind) => kind.key(),
+            CsvKind::Expenses => "expenses",
+        }
+    }
+
+    /// Finds the kind whose headers match the header row of a file.
+    fn from_headers(row: &[String]) -> Option<CsvKind> {
+        KINDS.iter().cloned().find(|kind| {
+            let headers = kind.headers();
+            // Excel starts "CSV UTF-8" files with a byte order mark
+            let cells = row.iter().enumerate().map(|(i, r)| if i == 0 { r.trim_start_matches('\u{feff}') } else { r.as_str() });
+            headers.len() == row.len() && headers.iter().zip(cells).all(|(h, r)| h.eq_ignore_ascii_case(r.trim()))
+        })
+    }
+}
+
+/// Outcome of checking or importing a CSV file.
+#[derive(Debug, Clone)]
+pub struct CsvReport {
+    pub kind: Option<CsvKind>,
+    pub valid_rows: usize,
+    /// Rows already in the itinerary, or with a UID already earlier in the file, which are not imported again.
+    pub duplicate_rows: usize,
+    /// Line numbers that failed validation, with the reason.
+    pub errors: Vec<(usize, String)>,
+}
+
+/// Quotes a field if it contains a separator, a quote or a line break.
+fn escape_field(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Formats one CSV row, terminated by CRLF as RFC 4180 asks for.
+fn write_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| escape_field(field)).collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Splits CSV content into rows, keeping the line number each row starts on.
+/// Quoted fields may contain separators, doubled quotes and line breaks.
+fn parse_rows(content: &str) -> Result<Vec<(usize, Vec<String>)>, (usize, String)> {
+    let mut rows = Vec::new();
+    let mut row: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                if !(row.len() == 1 && row[0].is_empty()) {
+                    rows.push((row_line, std::mem::take(&mut row)));
+                }
+                row.clear();
+                line += 1;
+                row_line = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err((row_line, "Unterminated quoted field".to_string()));
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push((row_line, row));
+    }
+
+    Ok(rows)
+}
+
+/// Turns a validated row into a booking.
+fn row_to_booking(row: &[String]) -> Booking {
+    Booking {
+        uid: row[0].trim().to_string(),
+        title: row[1].trim().to_string(),
+        start: row[2].trim().to_string(),
+        end: row[3].trim().to_string(),
+        location: row[4].trim().to_string(),
+        confirmation: row[5].trim().to_string(),
+        notes: row[6].to_string(),
+    }
+}
+
+/// Turns a validated row into an expense.
+fn row_to_expense(row: &[String]) -> Expense {
+    Expense {
+        date: row[0].trim().to_string(),
+        description: row[1].trim().to_string(),
+        category: row[2].trim().to_string(),
+        amount: row[3].trim().to_string(),
+        currency: row[4].trim().to_uppercase(),
+    }
+}
+
+/// Checks one data row, returning why it is invalid.
+fn validate_row(kind: CsvKind, row: &[String]) -> Result<(), String> {
+    let headers = kind.headers();
+    if row.len() != headers.len() {
+        return Err(format!("Expected {} columns, found {}", headers.len(), row.len()));
+    }
+
+    match kind {
+        CsvKind::Bookings(_) => {
+            if row[1].trim().is_empty() {
+                return Err(format!("'{}' is required", headers[1]));
+            }
+            for index in [2, 3].iter() {
+                let value = row[*index].trim();
+                if !value.is_empty() && util::parse_date(value).is_none() {
+                    return Err(format!("'{}' is not a valid date: '{}'", headers[*index], value));
+                }
+            }
+        }
+        CsvKind::Expenses => {
+            if util::parse_date(&row[0]).is_none() {
+                return Err(format!("'date' is not a valid date: '{}'", row[0].trim()));
+            }
+            if row[1].trim().is_empty() {
+                return Err("'description' is required".to_string());
+            }
+            // Checked like the amount fields on screen, so "NaN", "1e5" and the like are rejected
+            if row[3].trim().is_empty() || Rule::Amount.check(&row[3]).is_err() {
+                return Err(format!("'amount' is not an amount like 12.50: '{}'", row[3].trim()));
+            }
+            let currency = row[4].trim();
+            if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err(format!("'currency' must be a 3 letter code: '{}'", currency));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Identifies a booking for spotting rows imported before: by UID, or by title and dates if it has none.
+fn booking_key(booking: &Booking) -> String {
+    if booking.uid.is_empty() {
+        format!("{}|{}|{}", booking.title, booking.start, booking.end)
+    } else {
+        format!("uid {}", booking.uid)
+    }
+}
+
+/// Identifies an expense for spotting rows imported before.
+fn expense_key(expense: &Expense) -> String {
+    format!("{}|{}|{}|{}", expense.date, expense.description, expense.amount, expense.currency)
+}
+
+/// Identifies a validated row the way `booking_key` and `expense_key` identify stored entries.
+fn row_key(kind: CsvKind, row: &[String]) -> String {
+    match kind {
+        CsvKind::Bookings(_) => booking_key(&row_to_booking(row)),
+        CsvKind::Expenses => expense_key(&row_to_expense(row)),
+    }
+}
+
+/// Whether a validated row is not in `seen`, the keys of entries already imported. Rows with a UID
+/// are added to `seen`, so a UID repeated in the file counts as a duplicate too. Rows without one may
+/// repeat within a file, e.g. two coffees bought on the same day.
+fn is_new_row(kind: CsvKind, row: &[String], seen: &mut HashSet<String>) -> bool {
+    let key = row_key(kind, row);
+    if matches!(kind, CsvKind::Bookings(_)) && !row[0].trim().is_empty() {
+        seen.insert(key)
+    } else {
+        !seen.contains(&key)
+    }
+}
+
+/// Keys of the entries of this kind already in the itinerary. UIDs are checked across every booking kind.
+fn stored_keys(database: &Database, title: &str, kind: CsvKind) -> HashSet<String> {
+    match kind {
+        CsvKind::Bookings(_) => [BookingKind::Flight, BookingKind::Travel, BookingKind::Accommodation]
+            .iter()
+            .flat_map(|kind| database.list_bookings(title, *kind))
+            .map(|booking| booking_key(&booking))
+            .collect(),
+        CsvKind::Expenses => database.list_expenses(title).iter().map(expense_key).collect(),
+    }
+}
+
+/// Path of the CSV file for one kind, e.g. "<folder>/japan-2025-flights.csv".
+pub fn file_path(folder: &Path, stem: &str, kind: CsvKind) -> PathBuf {
+    folder.join(format!("{}-{}.csv", stem, kind.file_suffix()))
+}
+
+/// Writes an itinerary's flights, travel legs, accommodations and expenses to one CSV file each.
+pub fn export(database: &Database, title: &str, folder: &Path, stem: &str) -> Result<String, io::Error> {
+    if database.get_itinerary(title).is_none() {
+        return Ok("ERROR_NO_ITINERARY".to_string());
+    }
+
+    for kind in KINDS.iter() {
+        let mut content = write_row(kind.headers());
+
+        match kind {
+            CsvKind::Bookings(booking_kind) => {
+                for b in database.list_bookings(title, *booking_kind) {
+                    content.push_str(&write_row(&[
+                        b.uid.as_str(),
+                        b.title.as_str(),
+                        b.start.as_str(),
+                        b.end.as_str(),
+                        b.location.as_str(),
+                        b.confirmation.as_str(),
+                        b.notes.as_str(),
+                    ]));
+                }
+            }
+            CsvKind::Expenses => {
+                for e in database.list_expenses(title) {
+                    content.push_str(&write_row(&[
+                        e.date.as_str(),
+                        e.description.as_str(),
+                        e.category.as_str(),
+                        e.amount.as_str(),
+                        e.currency.as_str(),
+                    ]));
+                }
+            }
+        }
+
+        let path = file_path(folder, stem, *kind);
+        fs::write(&path, content)?;
+        log(Level::Info, &format!("Exported {} to {}", kind.file_suffix(), path.display()));
+    }
+
+    Ok("SUCCESS".to_string())
+}
+
+/// Validates a CSV file against an itinerary without importing anything.
+pub fn check(database: &Database, title: &str, path: &str) -> io::Result<CsvReport> {
+    let content = fs::read_to_string(path)?;
+    let mut report = CsvReport { kind: None, valid_rows: 0, duplicate_rows: 0, errors: Vec::new() };
+
+    let rows = match parse_rows(&content) {
+        Ok(rows) => rows,
+        Err(error) => {
+            report.errors.push(error);
+            return Ok(report);
+        }
+    };
+
+    let mut rows = rows.into_iter();
+    let kind = match rows.next() {
+        Some((_, header)) => match CsvKind::from_headers(&header) {
+            Some(kind) => kind,
+            None => {
+                report.errors.push((1, "Header row does not match any known CSV layout".to_string()));
+                return Ok(report);
+            }
+        },
+        None => {
+            report.errors.push((1, "File is empty".to_string()));
+            return Ok(report);
+        }
+    };
+    report.kind = Some(kind);
+
+    let mut seen = stored_keys(database, title, kind);
+    for (line, row) in rows {
+        match validate_row(kind, &row) {
+            Ok(()) => {
+                if is_new_row(kind, &row, &mut seen) {
+                    report.valid_rows += 1;
+                } else {
+                    report.duplicate_rows += 1;
+                }
+            }
+            Err(reason) => report.errors.push((line, reason)),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Imports a CSV file into a saved itinerary. Nothing is imported unless every row is valid,
+/// so a corrected file can be imported again. Rows already imported are skipped: bookings by UID,
+/// or by title and dates if they have none, and expenses by date, description, amount and currency.
+pub fn import(database: &Database, title: &str, path: &str) -> Result<(String, CsvReport), io::Error> {
+    if database.get_itinerary(title).is_none() {
+        return Ok(("ERROR_NO_ITINERARY".to_string(), check(database, title, path)?));
+    }
+
+    let report = check(database, title, path)?;
+    let kind = match report.kind {
+        Some(kind) if report.errors.is_empty() => kind,
+        _ => {
+            for (line, reason) in &report.errors {
+                log(Level::Warn, &format!("{} line {}: {}", path, line, reason));
+            }
+            return Ok(("ERROR_INVALID_ROWS".to_string(), report));
+        }
+    };
+
+    let content = fs::read_to_string(path)?;
+    let rows: Vec<Vec<String>> = parse_rows(&content)
+        .unwrap_or_default()
+        .into_iter()
+        .skip(1)
+        .map(|(_, row)| row)
+        .collect();
+
+    // Rows already imported are left out, as counted by `check`
+    let mut seen = stored_keys(database, title, kind);
+    let rows: Vec<Vec<String>> = rows.into_iter().filter(|row| is_new_row(kind, row, &mut seen)).collect();
+
+    let status = match kind {
+        CsvKind::Bookings(booking_kind) => {
+            let bookings: Vec<(BookingKind, Booking)> =
+                rows.iter().map(|row| (booking_kind, row_to_booking(row))).collect();
+            database.add_bookings(title, &bookings)?
+        }
+        CsvKind::Expenses => {
+            let expenses: Vec<Expense> = rows.iter().map(|row| row_to_expense(row)).collect();
+            database.add_expenses(title, &expenses)?
+        }
+    };
+
+    log(Level::Info, &format!("Imported {} row(s) from {} into '{}'", report.valid_rows, path, title));
+    Ok((status, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| field.to_string()).collect()
+    }
+
+    #[test]
+    fn write_row_quotes_fields_that_need_it() {
+        assert_eq!(write_row(&["plain", "a,b", "say \"hi\"", "two\nlines"]), "plain,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\"\r\n");
+    }
+
+    #[test]
+    fn parse_rows_reads_back_written_rows() {
+        let fields = ["plain", "a,b", "say \"hi\"", "two\nlines", ""];
+        let content = format!("{}{}", write_row(&fields), write_row(&["next"]));
+
+        let rows = parse_rows(&content).unwrap();
+        assert_eq!(rows, vec![(1, strings(&fields)), (3, strings(&["next"]))]);
+    }
+
+    #[test]
+    fn parse_rows_skips_blank_lines_and_reads_a_last_row_without_line_break() {
+        let rows = parse_rows("a,b\n\nc,d").unwrap();
+        assert_eq!(rows, vec![(1, strings(&["a", "b"])), (3, strings(&["c", "d"]))]);
+    }
+
+    #[test]
+    fn parse_rows_reports_an_unterminated_quote_on_its_row() {
+        assert_eq!(parse_rows("a,b\nc,\"open\nstill open"), Err((2, "Unterminated quoted field".to_string())));
+    }
+
+    #[test]
+    fn check_reports_each_invalid_row_by_line() {
+        let dir = std::env::temp_dir().join(format!("itinerary_planner_csv_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let database = Database::new(dir.join("database.json").to_string_lossy().to_string());
+        let csv = dir.join("flights.csv");
+        let content = "uid,flight,departure,arrival,route,confirmation,notes\n\
+            a1,BA123,2025-03-12,2025-03-12,LHR-CDG,XY12,\n\
+            a2,BA124,someday,,,,\n\
+            a3,,2025-03-12,,,,\n\
+            a1,BA125,,,,,\n\
+            a4,BA126\n\
+            a5,\"BA 127\nnight\",,,,,\n\
+            a6,BA128,2025-03-12,later,,,\n";
+        fs::write(&csv, content).unwrap();
+
+        let report = check(&database, "Trip", &csv.to_string_lossy()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.kind, Some(CsvKind::Bookings(BookingKind::Flight)));
+        assert_eq!(report.valid_rows, 2);
+        assert_eq!(report.duplicate_rows, 1);
+        assert_eq!(
+            report.errors,
+            vec![
+                (3, "'departure' is not a valid date: 'someday'".to_string()),
+                (4, "'flight' is required".to_string()),
+                (6, "Expected 7 columns, found 2".to_string()),
+                (9, "'arrival' is not a valid date: 'later'".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn validate_row_checks_expenses() {
+        let kind = CsvKind::Expenses;
+        assert_eq!(validate_row(kind, &strings(&["2025-03-12", "Taxi", "travel", "12.50", "eur"])), Ok(()));
+        assert_eq!(
+            validate_row(kind, &strings(&["2025-03-12", "Taxi", "travel", "twelve", "EUR"])),
+            Err("'amount' is not an amount like 12.50: 'twelve'".to_string())
+        );
+        assert_eq!(
+            validate_row(kind, &strings(&["2025-03-12", "Taxi", "travel", "12", "EURO"])),
+            Err("'currency' must be a 3 letter code: 'EURO'".to_string())
+        );
+        assert_eq!(
+            validate_row(kind, &strings(&["soon", "Taxi", "travel", "12", "EUR"])),
+            Err("'date' is not a valid date: 'soon'".to_string())
+        );
+    }
+
+    #[test]
+    fn import_skips_rows_without_uid_imported_before() {
+        let dir = std::env::temp_dir().join(format!("itinerary_planner_csv_import_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let database = Database::new(dir.join("database.json").to_string_lossy().to_string());
+        database.add_itinerary("Trip".to_string()).unwrap();
+        let csv = dir.join("expenses.csv");
+        let content = "date,description,category,amount,currency\n\
+            2025-03-12,Coffee,food,3.50,EUR\n\
+            2025-03-12,Coffee,food,3.50,EUR\n";
+        fs::write(&csv, content).unwrap();
+        let path = csv.to_string_lossy().to_string();
+
+        let (status, report) = import(&database, "Trip", &path).unwrap();
+        assert_eq!(status, "SUCCESS");
+        assert_eq!((report.valid_rows, report.duplicate_rows), (2, 0));
+
+        let (_, report) = import(&database, "Trip", &path).unwrap();
+        let expenses = database.list_expenses("Trip");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!((report.valid_rows, report.duplicate_rows), (0, 2));
+        assert_eq!(expenses.len(), 2);
+    }
+
+    #[test]
+    fn validate_row_rejects_amounts_that_are_not_money() {
+        for amount in ["NaN", "inf", "1e5", "-3", "3.505", ""].iter() {
+            let row = strings(&["2025-03-12", "Taxi", "travel", amount, "EUR"]);
+            assert!(validate_row(CsvKind::Expenses, &row).is_err(), "{}", amount);
+        }
+    }
+
+    #[test]
+    fn from_headers_skips_a_byte_order_mark() {
+        let header = strings(&["\u{feff}date", "description", "category", "amount", "currency"]);
+        assert_eq!(CsvKind::from_headers(&header), Some(CsvKind::Expenses));
+    }
+
+    #[test]
+    fn from_headers_ignores_case_and_surrounding_spaces() {
+        let header = strings(&["Date", " description ", "CATEGORY", "amount", "currency"]);
+        assert_eq!(CsvKind::from_headers(&header), Some(CsvKind::Expenses));
+        assert_eq!(CsvKind::from_headers(&strings(&["date", "description"])), None);
+    }
+}
@@ -1,19 +1,22 @@
 use crate::util;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{self, Read, Write};
 use std::path::Path;
 
 use chrono::prelude::*;
 use rand::Rng;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use crate::globals;
+use crate::history::{Edit, Session};
+use crate::logging::Level;
 
-/// Logs messages to the application's debug log.
-fn log(message: &str) {
-    globals::add_to_debug_log(message);
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
 }
 
 /// Generates a unique session ID using the current timestamp and a random value.
@@ -33,6 +36,58 @@ pub struct Database {
     id: i64,
     session_storage: HashMap<String, String>,
     session_storage_nested: HashMap<String, HashMap<String, String>>,
+    /// Session storage as last loaded from or saved to the database, to tell whether there are unsaved changes.
+    saved_session: Session,
+    /// ID of the draft in session storage.
+    draft_id: u64,
+    /// The other drafts being edited, set aside until switched to.
+    parked_drafts: Vec<ParkedDraft>,
+    next_draft_id: u64,
+}
+
+/// An itinerary being edited that is not in session storage right now.
+#[derive(Debug, Clone)]
+struct ParkedDraft {
+    id: u64,
+    session: Session,
+    saved: Session,
+}
+
+impl ParkedDraft {
+    fn has_unsaved_changes(&self) -> bool {
+        !self.session.is_empty() && self.session != self.saved
+    }
+}
+
+/// A draft with unsaved changes, as kept in the recovery file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UnsavedDraft {
+    #[serde(flatten)]
+    pub session: Session,
+    /// The draft as last loaded from or saved to the database, empty if it was never saved.
+    #[serde(default)]
+    pub saved: Session,
+}
+
+/// A draft as listed for switching between drafts.
+#[derive(Debug, Clone)]
+pub struct DraftSummary {
+    pub id: u64,
+    /// The itinerary title typed so far, or "Untitled".
+    pub title: String,
+    pub unsaved: bool,
+    /// Whether this is the draft in session storage.
+    pub active: bool,
+}
+
+/// Title shown for a draft.
+fn draft_title(session: &Session) -> String {
+    session
+        .fields
+        .get("title")
+        .filter(|title| !title.trim().is_empty())
+        .cloned()
+        .unwrap_or_else(|| "Untitled".to_string())
 }
 
 /// Checks if the database file exists at the given path.
@@ -41,18 +96,110 @@ pub fn check_for_database(path: &str) -> bool {
     let exists = Path::new(path).exists();
 
     if exists {
-        globals::add_to_debug_log("'./database.rs': Database found.");
+        log(Level::Info, "Database found.");
     } else {
-        globals::add_to_debug_log("ERROR in './database.rs': Database was not found. A new one will be created.");
+        log(Level::Warn, &format!("Database was not found at {}. A new one will be created.", path));
     }
 
     exists
 }
 
+/// Session storage keys with these prefixes belong to a screen, not to the itinerary being edited.
+const TRANSIENT_PREFIXES: [&str; 4] = ["import_", "export_", "flight_", "place_"];
+
+/// Whether a session storage key belongs to a screen rather than to the itinerary being edited.
+fn is_transient(key: &str) -> bool {
+    TRANSIENT_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+}
+
 /// Represents an itinerary entry in the database.
 #[derive(Serialize, Deserialize, Debug)]
 struct Itinerary {
+    /// The run the itinerary was created in.
     session_id: i64,
+    /// Identifies the itinerary across renames and bundle exports. Older itineraries have none.
+    id: i64,
+}
+
+/// The kinds of bookings an itinerary can hold. Each kind is stored as a list under its own key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookingKind {
+    Flight,
+    Travel,
+    Accommodation,
+}
+
+impl BookingKind {
+    /// Returns the key the bookings of this kind are stored under in an itinerary.
+    pub fn key(&self) -> &'static str {
+        match self {
+            BookingKind::Flight => "flights",
+            BookingKind::Travel => "travel",
+            BookingKind::Accommodation => "accommodations",
+        }
+    }
+
+    /// Returns a human readable name for this kind of booking.
+    pub fn label(&self) -> &'static str {
+        match self {
+            BookingKind::Flight => "Flight",
+            BookingKind::Travel => "Travel",
+            BookingKind::Accommodation => "Accommodation",
+        }
+    }
+}
+
+/// Represents money spent during an itinerary.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Expense {
+    pub date: String,
+    pub description: String,
+    #[serde(default)]
+    pub category: String,
+    pub amount: String,
+    pub currency: String,
+}
+
+/// Represents a place to visit during an itinerary, e.g. a museum or a restaurant.
+/// The aliases read places stored in the booking layout.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Place {
+    #[serde(alias = "title")]
+    pub name: String,
+    #[serde(default, alias = "location")]
+    pub address: String,
+    #[serde(default, alias = "start")]
+    pub date: String,
+    #[serde(default)]
+    pub time: String,
+    #[serde(default)]
+    pub cost: String,
+    #[serde(default)]
+    pub notes: String,
+}
+
+/// Represents a destination (country) visited during an itinerary.
+#[derive(Debug, Clone, Default)]
+pub struct Destination {
+    pub title: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+/// Represents a single flight, travel leg or accommodation within an itinerary.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Booking {
+    #[serde(default)]
+    pub uid: String,
+    pub title: String,
+    pub start: String,
+    pub end: String,
+    #[serde(default)]
+    pub location: String,
+    #[serde(default)]
+    pub confirmation: String,
+    #[serde(default)]
+    pub notes: String,
 }
 
 impl Database {
@@ -60,11 +207,8 @@ impl Database {
     pub fn new(path: String) -> Database {
         if !check_for_database(&path) {
             match Database::initiate_db(&path) {
-                Ok(_) => globals::add_to_debug_log(&format!("Database created successfully at {}", path)),
-                Err(e) => {
-                    globals::add_to_debug_log(&format!("Failed to create database at {}", path));
-                    globals::add_to_debug_log(&e.to_string());
-                }
+                Ok(_) => log(Level::Info, &format!("Database created successfully at {}", path)),
+                Err(e) => log(Level::Error, &format!("Failed to create database at {}: {}", path, e)),
             }
         }
 
@@ -73,6 +217,10 @@ impl Database {
             id: generate_session_id(),
             session_storage: HashMap::new(),
             session_storage_nested: HashMap::new(),
+            saved_session: Session::default(),
+            draft_id: 0,
+            parked_drafts: Vec::new(),
+            next_draft_id: 1,
         }
     }
 
@@ -93,9 +241,16 @@ impl Database {
 	}
 
     /// Updates or inserts a key-value pair in the session storage.
+    /// Changes to the itinerary being edited are recorded for undo.
     pub fn set_kv_session_storage(&mut self, key: &str, value: &str) {
+        if self.session_storage.get(key).map(|v| v.as_str()) == Some(value) {
+            return;
+        }
+        let before = self.session_snapshot();
+
         let key = key.to_string();
         let value = value.to_string();
+        let edited_key = key.clone();
 
         match self.session_storage.entry(key) {
             std::collections::hash_map::Entry::Occupied(mut entry) => {
@@ -105,9 +260,180 @@ impl Database {
                 entry.insert(value);
             }
         }
+
+        if !is_transient(&edited_key) {
+            self.record_session(Some(&edited_key), before);
+        }
+    }
+
+    /// Copies the itinerary being edited out of session storage, leaving out transient keys.
+    pub fn session_snapshot(&self) -> Session {
+        Session {
+            fields: self
+                .session_storage
+                .iter()
+                .filter(|(key, _)| !is_transient(key))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            destinations: self.session_storage_nested.clone(),
+        }
+    }
+
+    /// Puts a copy taken with `session_snapshot` back. Transient keys are kept as they are.
+    fn restore_session(&mut self, session: &Session) {
+        self.session_storage.retain(|key, _| is_transient(key));
+        for (key, value) in &session.fields {
+            self.session_storage.insert(key.clone(), value.clone());
+        }
+        self.session_storage_nested = session.destinations.clone();
+    }
+
+    /// Whether the itinerary being edited has changes that are not saved in the database.
+    pub fn has_unsaved_changes(&self) -> bool {
+        let session = self.session_snapshot();
+        !session.is_empty() && session != self.saved_session
+    }
+
+    /// Throws away the unsaved changes to the itinerary being edited. Can be undone.
+    pub fn discard_session_storage(&mut self) {
+        let before = self.session_snapshot();
+        self.restore_session(&Session::default());
+        self.saved_session = Session::default();
+        self.record_session(None, before);
+    }
+
+    /// Lists every draft, the one in session storage included, in the order they were started.
+    /// Empty drafts other than the one in session storage are not kept.
+    pub fn list_drafts(&self) -> Vec<DraftSummary> {
+        let active = self.session_snapshot();
+        let mut drafts: Vec<DraftSummary> = self
+            .parked_drafts
+            .iter()
+            .map(|draft| DraftSummary {
+                id: draft.id,
+                title: draft_title(&draft.session),
+                unsaved: draft.has_unsaved_changes(),
+                active: false,
+            })
+            .collect();
+        drafts.push(DraftSummary {
+            id: self.draft_id,
+            title: draft_title(&active),
+            unsaved: self.has_unsaved_changes(),
+            active: true,
+        });
+
+        drafts.sort_by_key(|draft| draft.id);
+        drafts
+    }
+
+    /// Sets the draft in session storage aside and starts an empty one in its place.
+    /// An empty draft is dropped rather than set aside.
+    fn park_active(&mut self) {
+        let session = self.session_snapshot();
+        if !session.is_empty() {
+            self.parked_drafts.push(ParkedDraft { id: self.draft_id, session, saved: self.saved_session.clone() });
+        }
+
+        self.restore_session(&Session::default());
+        self.saved_session = Session::default();
+        self.draft_id = self.next_draft_id;
+        self.next_draft_id += 1;
+    }
+
+    /// Starts a new, empty draft, keeping the current one to switch back to.
+    pub fn new_draft(&mut self) {
+        if !self.session_snapshot().is_empty() {
+            self.park_active();
+        }
+    }
+
+    /// Makes the draft with the given ID the one in session storage. Returns false if there is no such draft.
+    pub fn switch_draft(&mut self, id: u64) -> bool {
+        if id == self.draft_id {
+            return true;
+        }
+        let index = match self.parked_drafts.iter().position(|draft| draft.id == id) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let draft = self.parked_drafts.remove(index);
+        self.park_active();
+        self.restore_session(&draft.session);
+        self.saved_session = draft.saved;
+        self.draft_id = draft.id;
+        true
+    }
+
+    /// Saves every draft with unsaved changes that passes `check`. Stops at the first draft that fails
+    /// it or can't be saved and returns its title and the reason, e.g. a status like "ERROR_NO_TITLE".
+    pub fn save_all_drafts<F: Fn(&Session) -> Result<(), String>>(&mut self, check: F) -> Result<(), (String, String)> {
+        let ids: Vec<u64> = self.list_drafts().into_iter().filter(|draft| draft.unsaved).map(|draft| draft.id).collect();
+
+        for id in ids {
+            self.switch_draft(id);
+            let session = self.session_snapshot();
+            let title = draft_title(&session);
+            if let Err(message) = check(&session) {
+                return Err((title, message));
+            }
+            match self.store_session_storage_in_database() {
+                Ok(status) if status == "SUCCESS" => {}
+                Ok(status) => return Err((title, status)),
+                Err(e) => return Err((title, e.to_string())),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Throws away every draft. The one in session storage can be brought back with undo.
+    pub fn discard_all_drafts(&mut self) {
+        self.parked_drafts.clear();
+        self.discard_session_storage();
+    }
+
+    /// Every draft with unsaved changes, the one in session storage first, e.g. for the recovery file.
+    pub fn unsaved_drafts(&self) -> Vec<UnsavedDraft> {
+        let mut drafts = Vec::new();
+        if self.has_unsaved_changes() {
+            drafts.push(UnsavedDraft { session: self.session_snapshot(), saved: self.saved_session.clone() });
+        }
+        drafts.extend(
+            self.parked_drafts
+                .iter()
+                .filter(|draft| draft.has_unsaved_changes())
+                .map(|draft| UnsavedDraft { session: draft.session.clone(), saved: draft.saved.clone() }),
+        );
+        drafts
+    }
+
+    /// Adds recovered drafts. The first goes into session storage if that is empty, the rest are set aside.
+    /// Each keeps the saved state it was recovered with, so only its changes since then show as unsaved.
+    pub fn restore_drafts(&mut self, drafts: &[UnsavedDraft]) {
+        for draft in drafts.iter().filter(|draft| !draft.session.is_empty()) {
+            if self.session_snapshot().is_empty() {
+                self.restore_session(&draft.session);
+                self.saved_session = draft.saved.clone();
+            } else {
+                let id = self.next_draft_id;
+                self.next_draft_id += 1;
+                self.parked_drafts.push(ParkedDraft { id, session: draft.session.clone(), saved: draft.saved.clone() });
+            }
+        }
+    }
+
+    /// Records the change made to the draft in session storage since `before` was taken, if any.
+    fn record_session(&self, key: Option<&str>, before: Session) {
+        let after = self.session_snapshot();
+        if before != after {
+            globals::get_history().record(Edit::Session { draft: self.draft_id, key: key.map(|k| k.to_string()), before, after });
+        }
     }
 
     pub fn add_current_destination_to_session_storage(&mut self) {
+        let before = self.session_snapshot();
         let destination_title = self.session_storage.get("current_country_title");
         let destination_start_date = self.session_storage.get("current_country_start_date");
         let destination_end_date = self.session_storage.get("current_country_end_date");
@@ -123,14 +449,84 @@ impl Database {
         	self.session_storage.remove("current_country_start_date");
         	self.session_storage.remove("current_country_end_date");
         }
+        self.record_session(None, before);
 
         for (k, v) in &self.session_storage_nested {
         	let formatted_map = util::format_hashmap(&v);
-        	log(&format!("{}: [{}]", k, &formatted_map));
+        	log(Level::Debug, &format!("{}: [{}]", k, &formatted_map));
+        }
+    }
+
+    /// Title of the itinerary being edited, if one was typed.
+    fn session_title(&self) -> Option<String> {
+        self.session_storage.get("title").filter(|title| !title.trim().is_empty()).cloned()
+    }
+
+    /// Trimmed value of a session storage key, empty if it isn't set.
+    fn session_field(&self, key: &str) -> String {
+        self.session_storage.get(key).map(|value| value.trim().to_string()).unwrap_or_default()
+    }
+
+    /// Adds the flight typed on the FLIGHTS screen to the saved itinerary being edited,
+    /// then clears the screen's "flight_*" keys. The itinerary has to be saved first.
+    pub fn add_flight_from_session_storage(&mut self) -> Result<String, io::Error> {
+        let title = match self.session_title() {
+            Some(title) => title,
+            None => return Ok("ERROR_NO_TITLE".to_string()),
+        };
+        let from = self.session_field("flight_from").to_uppercase();
+        let to = self.session_field("flight_to").to_uppercase();
+        let date_time = |date: &str, time: &str| format!("{} {}", self.session_field(date), self.session_field(time)).trim().to_string();
+
+        let booking = Booking {
+            title: format!("{} {}-{}", self.session_field("flight_number"), from, to),
+            start: date_time("flight_departure_date", "flight_departure_time"),
+            end: date_time("flight_arrival_date", "flight_arrival_time"),
+            location: from,
+            confirmation: self.session_field("flight_confirmation"),
+            notes: self.session_field("flight_notes"),
+            ..Booking::default()
+        };
+
+        let status = self.add_bookings(&title, &[(BookingKind::Flight, booking)])?;
+        if status == "SUCCESS" {
+            self.session_storage.retain(|key, _| !key.starts_with("flight_"));
+        }
+        Ok(status)
+    }
+
+    /// Adds the place typed on the PLACES screen to the saved itinerary being edited,
+    /// then clears the screen's "place_*" keys. The itinerary has to be saved first.
+    pub fn add_place_from_session_storage(&mut self) -> Result<String, io::Error> {
+        let title = match self.session_title() {
+            Some(title) => title,
+            None => return Ok("ERROR_NO_TITLE".to_string()),
+        };
+        let place = Place {
+            name: self.session_field("place_name"),
+            address: self.session_field("place_address"),
+            date: self.session_field("place_date"),
+            time: self.session_field("place_time"),
+            cost: self.session_field("place_cost"),
+            notes: self.session_field("place_notes"),
+        };
+
+        let status = self.add_places(&title, &[place])?;
+        if status == "SUCCESS" {
+            self.session_storage.retain(|key, _| !key.starts_with("place_"));
         }
+        Ok(status)
+    }
+
+    /// Saves the itinerary being edited and clears session storage. Undone as a single edit.
+    pub fn store_session_storage_in_database(&mut self) -> Result<String, io::Error> {
+        globals::get_history().begin_group();
+        let result = self.write_session_storage();
+        globals::get_history().end_group();
+        result
     }
- 
-	pub fn store_session_storage_in_database(&mut self) -> Result<String, io::Error> {
+
+	fn write_session_storage(&mut self) -> Result<String, io::Error> {
 	    let session_storage = self.get_session_storage();
 
 	    if let Some(title) = session_storage.get("title") {
@@ -140,36 +536,357 @@ impl Database {
 	        // Ensure itinerary exists in json_data before updating
 	        if !json_data.get(title).is_some() {
 	            self.add_itinerary(title.to_string())?;
-	            json_data[title] = json!(Itinerary { session_id: self.id });
+	            json_data[title] = json!(Itinerary { session_id: self.id, id: self.new_itinerary_id() });
 	        }
 
 	        // Now that the itinerary exists, update it
 	        if let Some(itinerary) = json_data.get_mut(title) {
 	            if let Value::Object(ref mut map) = itinerary {
 	                for (key, value) in &session_storage {
-	                	if key.clone() != "title" {
+	                	// Fields that only drive the IMPORT/EXPORT screens are not part of the itinerary
+	                	if key.clone() != "title" && !is_transient(key) {
 							map.insert(key.clone(), Value::String(value.clone()));
 	                	}
 	                }
+
+	                // Merge the destinations added during this session
+	                let destinations = map.entry("destinations".to_string()).or_insert_with(|| json!({}));
+	                if let Value::Object(destinations) = destinations {
+	                    for (name, fields) in &self.session_storage_nested {
+	                        destinations.insert(name.clone(), json!(fields));
+	                    }
+	                }
 	            }
 	        }
 
 	        // Write the updated JSON back to the file
-	        let updated_json = serde_json::to_string_pretty(&json_data)?;
-	        let mut file = File::create(&self.path)?;
-	        file.write_all(updated_json.as_bytes())?;
-	        file.flush()?; // Ensure the changes are written to disk
+	        self.write_db(&json_data)?;
 
 	    } else {
 	        return Ok("ERROR_NO_TITLE".to_string());
 	    }
 
+	    let before = self.session_snapshot();
 	    self.session_storage = HashMap::new();
+	    self.session_storage_nested = HashMap::new();
+	    self.saved_session = Session::default();
+	    self.record_session(None, before);
 	    Ok("SUCCESS".to_string())
 	}
 
+    /// Retrieves a saved itinerary by title.
+    pub fn get_itinerary(&self, title: &str) -> Option<Value> {
+        let db_data = self.read_db().ok()?;
+        let json_data: Value = serde_json::from_str(&db_data).ok()?;
+        json_data.get(title).cloned()
+    }
+
+    /// Loads a saved itinerary into session storage so it can be edited on the CREATE screen.
+    /// A draft already open for that itinerary is switched to instead, keeping its unsaved changes,
+    /// and the draft in session storage is set aside rather than replaced.
+    /// Returns false if no itinerary has that title.
+    pub fn load_itinerary_into_session_storage(&mut self, title: &str) -> bool {
+        if self.session_storage.get("title").map(|t| t.as_str()) == Some(title) {
+            return true;
+        }
+        let open_draft = self
+            .parked_drafts
+            .iter()
+            .find(|draft| draft.session.fields.get("title").map(|t| t.as_str()) == Some(title))
+            .map(|draft| draft.id);
+        if let Some(id) = open_draft {
+            return self.switch_draft(id);
+        }
+
+        let itinerary = match self.get_itinerary(title) {
+            Some(Value::Object(map)) => map,
+            _ => return false,
+        };
+
+        self.new_draft();
+        let before = self.session_snapshot();
+        self.session_storage.insert("title".to_string(), title.to_string());
+
+        // Only the flat text fields are edited through session storage
+        for (key, value) in &itinerary {
+            if let Value::String(value) = value {
+                self.session_storage.insert(key.clone(), value.clone());
+            }
+        }
+
+        for destination in self.list_destinations(title) {
+            let map = self.session_storage_nested.entry(destination.title).or_insert_with(HashMap::new);
+            map.insert("start_date".to_string(), destination.start_date);
+            map.insert("end_date".to_string(), destination.end_date);
+        }
+        self.saved_session = self.session_snapshot();
+        self.record_session(None, before);
+
+        true
+    }
+
+    /// Lists the bookings of the given kind stored in an itinerary.
+    pub fn list_bookings(&self, title: &str, kind: BookingKind) -> Vec<Booking> {
+        self.list_entries(title, kind.key())
+    }
+
+    /// Lists the places to visit stored in an itinerary.
+    pub fn list_places(&self, title: &str) -> Vec<Place> {
+        self.list_entries(title, "places")
+    }
+
+    /// Deserializes the list stored under `key` in an itinerary, or an empty list if there is none.
+    /// Entries that can't be read are logged and left out, so one bad entry doesn't hide the others.
+    fn list_entries<T: DeserializeOwned>(&self, title: &str, key: &str) -> Vec<T> {
+        let entries = match self.get_itinerary(title).and_then(|itinerary| itinerary.get(key).cloned()) {
+            Some(Value::Array(entries)) => entries,
+            Some(_) => {
+                log(Level::Error, &format!("The {} of '{}' are not a list.", key, title));
+                return Vec::new();
+            }
+            None => return Vec::new(),
+        };
+
+        entries
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, entry)| match serde_json::from_value(entry) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    log(Level::Error, &format!("Entry {} of the {} of '{}' could not be read: {}", index + 1, key, title, e));
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Lists the destinations stored in an itinerary, ordered by start date.
+    pub fn list_destinations(&self, title: &str) -> Vec<Destination> {
+        let itinerary = match self.get_itinerary(title) {
+            Some(itinerary) => itinerary,
+            None => return Vec::new(),
+        };
+
+        let field = |fields: &Value, key: &str| fields.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+
+        let mut destinations: Vec<Destination> = match itinerary.get("destinations").and_then(|d| d.as_object()) {
+            Some(map) => map
+                .iter()
+                .map(|(name, fields)| Destination {
+                    title: name.clone(),
+                    start_date: field(fields, "start_date"),
+                    end_date: field(fields, "end_date"),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        // By date rather than text, since the date format is configurable; unreadable dates go last
+        destinations.sort_by_key(|destination| {
+            let start = util::parse_date(&destination.start_date);
+            (start.is_none(), start, destination.start_date.clone())
+        });
+        destinations
+    }
+
+    /// Collects the UIDs of every booking already stored in an itinerary.
+    pub fn booking_uids(&self, title: &str) -> HashSet<String> {
+        [BookingKind::Flight, BookingKind::Travel, BookingKind::Accommodation]
+            .iter()
+            .flat_map(|kind| self.list_bookings(title, *kind))
+            .map(|booking| booking.uid)
+            .filter(|uid| !uid.is_empty())
+            .collect()
+    }
+
+    /// Appends bookings to a saved itinerary in a single write.
+    pub fn add_bookings(&self, title: &str, bookings: &[(BookingKind, Booking)]) -> Result<String, io::Error> {
+        let mut entries = Vec::new();
+        for (kind, booking) in bookings {
+            entries.push((kind.key(), serde_json::to_value(booking)?));
+        }
+        self.append_entries(title, &entries)
+    }
+
+    /// Lists the expenses recorded for an itinerary.
+    pub fn list_expenses(&self, title: &str) -> Vec<Expense> {
+        self.list_entries(title, "expenses")
+    }
+
+    /// Appends places to visit to a saved itinerary in a single write.
+    pub fn add_places(&self, title: &str, places: &[Place]) -> Result<String, io::Error> {
+        let mut entries = Vec::new();
+        for place in places {
+            entries.push(("places", serde_json::to_value(place)?));
+        }
+        self.append_entries(title, &entries)
+    }
+
+    /// Appends expenses to a saved itinerary in a single write.
+    pub fn add_expenses(&self, title: &str, expenses: &[Expense]) -> Result<String, io::Error> {
+        let mut entries = Vec::new();
+        for expense in expenses {
+            entries.push(("expenses", serde_json::to_value(expense)?));
+        }
+        self.append_entries(title, &entries)
+    }
+
+    /// Appends each value to the list stored under its key in a saved itinerary.
+    fn append_entries(&self, title: &str, entries: &[(&str, Value)]) -> Result<String, io::Error> {
+        let db_data = self.read_db()?;
+        let mut json_data: Value = serde_json::from_str(&db_data).unwrap_or_else(|_| json!({}));
+
+        let map = match json_data.get_mut(title) {
+            Some(Value::Object(map)) => map,
+            _ => return Ok("ERROR_NO_ITINERARY".to_string()),
+        };
+
+        for (key, value) in entries {
+            let list = map.entry(key.to_string()).or_insert_with(|| json!([]));
+            if let Value::Array(list) = list {
+                list.push(value.clone());
+            }
+        }
+
+        self.write_db(&json_data)?;
+        Ok("SUCCESS".to_string())
+    }
+
+    /// Finds the title of the itinerary with the given ID, if any.
+    pub fn itinerary_title_by_id(&self, id: i64) -> Option<String> {
+        let db_data = self.read_db().ok()?;
+        let json_data: Value = serde_json::from_str(&db_data).ok()?;
+
+        json_data
+            .as_object()?
+            .iter()
+            .find(|(_, itinerary)| itinerary.get("id").and_then(|v| v.as_i64()) == Some(id))
+            .map(|(title, _)| title.clone())
+    }
+
+    /// Stores an itinerary under the given title, replacing any itinerary already stored there.
+    pub fn put_itinerary(&self, title: &str, itinerary: Value) -> io::Result<()> {
+        let db_data = self.read_db()?;
+        let mut json_data: Value = serde_json::from_str(&db_data).unwrap_or_else(|_| json!({}));
+
+        if let Some(map) = json_data.as_object_mut() {
+            map.insert(title.to_string(), itinerary);
+        }
+
+        self.write_db(&json_data)
+    }
+
+    /// Stores an itinerary under `title` in place of the one stored as `replaced`, in a single write.
+    pub fn replace_itinerary(&self, replaced: &str, title: &str, itinerary: Value) -> io::Result<()> {
+        let db_data = self.read_db()?;
+        let mut json_data: Value = serde_json::from_str(&db_data).unwrap_or_else(|_| json!({}));
+
+        if let Some(map) = json_data.as_object_mut() {
+            map.remove(replaced);
+            map.insert(title.to_string(), itinerary);
+        }
+
+        self.write_db(&json_data)
+    }
+
+    /// Generates a fresh itinerary ID, e.g. for an imported copy of an existing itinerary.
+    pub fn new_itinerary_id(&self) -> i64 {
+        generate_session_id()
+    }
+
+    /// Writes the given JSON back to the database file, recording the change for undo.
+    /// Fails without writing if the current content can't be read, since undo would have nothing to put back.
+    fn write_db(&self, json_data: &Value) -> io::Result<()> {
+        let before = self.read_db()?;
+        let updated_json = serde_json::to_string_pretty(json_data)?;
+        self.write_raw(&updated_json)?;
+
+        globals::get_history().record(Edit::Database { before, after: updated_json });
+        Ok(())
+    }
+
+    /// Replaces the database file content as is.
+    fn write_raw(&self, content: &str) -> io::Result<()> {
+        let mut file = File::create(&self.path)?;
+        file.write_all(content.as_bytes())?;
+        file.flush()
+    }
+
+    /// Puts back the state before an edit (`undo`) or after it (`!undo`), without recording anything.
+    fn apply_edit(&mut self, edit: &Edit, undo: bool) -> io::Result<()> {
+        match edit {
+            Edit::Session { draft, before, after, .. } => {
+                let session = if undo { before } else { after };
+                // The edit may belong to a draft that was set aside since
+                if *draft == self.draft_id {
+                    self.restore_session(session);
+                } else if let Some(parked) = self.parked_drafts.iter_mut().find(|parked| parked.id == *draft) {
+                    parked.session = session.clone();
+                } else {
+                    self.parked_drafts.push(ParkedDraft { id: *draft, session: session.clone(), saved: Session::default() });
+                }
+                self.parked_drafts.retain(|parked| !parked.session.is_empty());
+                Ok(())
+            }
+            Edit::Database { before, after } => self.write_raw(if undo { before } else { after }),
+            Edit::Group(edits) => {
+                if undo {
+                    for edit in edits.iter().rev() {
+                        self.apply_edit(edit, true)?;
+                    }
+                } else {
+                    for edit in edits {
+                        self.apply_edit(edit, false)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Reverts the most recent edit. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> io::Result<bool> {
+        let edit = match globals::get_history().take_undo() {
+            Some(edit) => edit,
+            None => return Ok(false),
+        };
+
+        match self.apply_edit(&edit, true) {
+            Ok(()) => {
+                globals::get_history().push_redo(edit);
+                Ok(true)
+            }
+            Err(e) => {
+                globals::get_history().push_undo(edit);
+                Err(e)
+            }
+        }
+    }
+
+    /// Applies the most recently undone edit again. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> io::Result<bool> {
+        let edit = match globals::get_history().take_redo() {
+            Some(edit) => edit,
+            None => return Ok(false),
+        };
+
+        match self.apply_edit(&edit, false) {
+            Ok(()) => {
+                globals::get_history().push_undo(edit);
+                Ok(true)
+            }
+            Err(e) => {
+                globals::get_history().push_redo(edit);
+                Err(e)
+            }
+        }
+    }
+
     /// Creates a new empty database file at the specified path.
     fn initiate_db(path: &str) -> io::Result<()> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)?;
+        }
         let mut file = File::create(path)?;
         file.write_all(b"{\n}")?;
         Ok(())
@@ -189,7 +906,6 @@ impl Database {
     }
 
     /// Retrieves the database file path.
-    #[allow(dead_code)]
     pub fn get_path(&self) -> String {
         self.path.clone()
     }
@@ -200,8 +916,8 @@ impl Database {
         let mut itineraries: Value = match serde_json::from_str(&db_data) {
             Ok(itineraries) => itineraries,
             Err(_) => {
-                globals::add_to_debug_log(&format!(
-                    "ERROR In 'database.rs': Could not parse database JSON file at path {}",
+                log(Level::Error, &format!(
+                    "Could not parse database JSON file at path {}",
                     self.path
                 ));
                 return Ok("ERROR_ALREADY_EXISTS".to_string());
@@ -210,19 +926,42 @@ impl Database {
 
         if let Some(map) = itineraries.as_object_mut() {
             if map.contains_key(&title) {
-                globals::add_to_debug_log("ERROR In 'database.rs': itinerary already exists");
+                log(Level::Warn, &format!("Itinerary '{}' already exists", title));
                 return Ok("ERROR_ALREADY_EXISTS".to_string());
             }
-            map.insert(title, json!(Itinerary { session_id: self.id }));
+            map.insert(title, json!(Itinerary { session_id: self.id, id: self.new_itinerary_id() }));
         }
 
-        let json_data = serde_json::to_string_pretty(&itineraries)?;
-
-        // Open the file with write permissions and truncate it to overwrite existing content
-        let mut file = File::create(&self.path)?;
-        file.write_all(json_data.as_bytes())?;
-        file.flush()?;
+        self.write_db(&itineraries)?;
 
         Ok("SUCCESS".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn places_added_are_listed_back() {
+        let dir = std::env::temp_dir().join(format!("itinerary_planner_database_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let database = Database::new(dir.join("database.json").to_string_lossy().to_string());
+        database.add_itinerary("Trip".to_string()).unwrap();
+
+        let place = Place {
+            name: "Louvre".to_string(),
+            address: "Rue de Rivoli".to_string(),
+            date: "2025-03-12".to_string(),
+            time: "10:00".to_string(),
+            cost: "17".to_string(),
+            notes: "Book ahead".to_string(),
+        };
+        assert_eq!(database.add_places("Trip", &[place.clone()]).unwrap(), "SUCCESS");
+        let places = database.list_places("Trip");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(places, vec![place]);
+    }
+}
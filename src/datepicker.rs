@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use chrono::{Datelike, Local, NaiveDate};
+use gtk::prelude::*;
+use gtk::{ApplicationWindow, Calendar, CalendarDisplayOptions, Entry, EntryIconPosition, Popover};
+
+use crate::{globals, keymap, util};
+
+/// Style class marking entries that have a date picker.
+const CLASS: &str = "date-entry";
+
+/// What the calendar highlights: the itinerary's dates and the days already booked to destinations.
+struct Highlights {
+    itinerary: Option<(NaiveDate, NaiveDate)>,
+    /// Dates of the other destinations of the itinerary being edited, with their names.
+    booked: Vec<(NaiveDate, NaiveDate, String)>,
+}
+
+impl Highlights {
+    /// Reads the dates of the itinerary being edited. The destination open on the DESTINATIONS screen
+    /// is left out, so its own dates don't show as booked.
+    fn load() -> Highlights {
+        let (fields, destinations) = {
+            let database = globals::get_database();
+            let db_instance = database.lock().unwrap();
+            let session = db_instance.session_snapshot();
+            (db_instance.get_session_storage(), session.destinations)
+        };
+        let range = |fields: &HashMap<String, String>, start: &str, end: &str| {
+            let start = fields.get(start).and_then(|date| util::parse_date(date))?;
+            let end = fields.get(end).and_then(|date| util::parse_date(date))?;
+            Some((start, end))
+        };
+
+        let editing = fields.get("current_country_title").map(|title| title.trim().to_string());
+        let mut booked: Vec<(NaiveDate, NaiveDate, String)> = destinations
+            .iter()
+            .filter(|(name, _)| editing.as_deref() != Some(name.as_str()))
+            .filter_map(|(name, dates)| range(dates, "start_date", "end_date").map(|(start, end)| (start, end, name.clone())))
+            .collect();
+        booked.sort();
+
+        Highlights { itinerary: range(&fields, "start_date", "end_date"), booked }
+    }
+
+    fn in_itinerary(&self, date: NaiveDate) -> bool {
+        self.itinerary.map_or(false, |(start, end)| start <= date && date <= end)
+    }
+
+    fn booked_to(&self, date: NaiveDate) -> Option<&str> {
+        self.booked.iter().find(|(start, end, _)| *start <= date && date <= *end).map(|(_, _, name)| name.as_str())
+    }
+}
+
+/// The date shown in the calendar. GTK counts months from 0.
+fn calendar_date(calendar: &Calendar) -> Option<NaiveDate> {
+    let (year, month, day) = calendar.date();
+    NaiveDate::from_ymd_opt(year as i32, month + 1, day)
+}
+
+/// Marks the days of the shown month that are part of the itinerary.
+fn mark_itinerary(calendar: &Calendar, highlights: &Highlights) {
+    calendar.clear_marks();
+    let (year, month, _) = calendar.date();
+    for day in 1..=31 {
+        if let Some(date) = NaiveDate::from_ymd_opt(year as i32, month + 1, day) {
+            if highlights.in_itinerary(date) {
+                calendar.mark_day(day);
+            }
+        }
+    }
+}
+
+/// Writes the date picked in the calendar to the entry in the configured format and closes the popover.
+fn pick(calendar: &Calendar, entry: &Entry, popover: &Popover) {
+    if let Some(date) = calendar_date(calendar) {
+        entry.set_text(&util::format_date(date));
+    }
+    popover.popdown();
+    entry.grab_focus();
+}
+
+/// Opens the calendar under a date entry, on the entry's date, or else the itinerary's start or today.
+/// Days of the itinerary are in bold and days booked to a destination show its name underneath.
+/// Double-click or Enter picks a day, ESC closes the calendar.
+pub fn open(entry: &Entry) {
+    let highlights = Highlights::load();
+    let shown = util::parse_date(&entry.text())
+        .or_else(|| highlights.itinerary.map(|(start, _)| start))
+        .unwrap_or_else(|| Local::now().naive_local().date());
+
+    let calendar = Calendar::new();
+    calendar.set_display_options(
+        CalendarDisplayOptions::SHOW_HEADING | CalendarDisplayOptions::SHOW_DAY_NAMES | CalendarDisplayOptions::SHOW_DETAILS,
+    );
+    calendar.set_detail_width_chars(4);
+    calendar.set_detail_height_rows(1);
+    calendar.select_month(shown.month0(), shown.year() as u32);
+    calendar.select_day(shown.day());
+
+    mark_itinerary(&calendar, &highlights);
+    let highlights = Rc::new(highlights);
+    calendar.set_detail_func({
+        let highlights = highlights.clone();
+        move |_, year, month, day| {
+            let date = NaiveDate::from_ymd_opt(year as i32, month + 1, day)?;
+            highlights.booked_to(date).map(|name| name.chars().take(4).collect())
+        }
+    });
+    calendar.connect_month_changed({
+        let highlights = highlights.clone();
+        move |calendar| mark_itinerary(calendar, &highlights)
+    });
+
+    let popover = Popover::new(Some(entry));
+    popover.add(&calendar);
+
+    calendar.connect_day_selected_double_click({
+        let entry = entry.clone();
+        let popover = popover.clone();
+        move |calendar| pick(calendar, &entry, &popover)
+    });
+    calendar.connect_key_press_event({
+        let entry = entry.clone();
+        let popover = popover.clone();
+        move |calendar, event| match keymap::Chord::from_event(event).key.as_str() {
+            "return" | "kp_enter" => {
+                pick(calendar, &entry, &popover);
+                Inhibit(true)
+            }
+            _ => Inhibit(false),
+        }
+    });
+    // A new popover is built each time, so let go of this one once it closes
+    popover.connect_closed(|popover| popover.set_relative_to(None::<&gtk::Widget>));
+
+    popover.show_all();
+    popover.popup();
+    calendar.grab_focus();
+}
+
+/// Gives a date entry a calendar button, which opens the date picker like the pick date key does.
+pub fn attach(entry: &Entry) {
+    entry.style_context().add_class(CLASS);
+    entry.set_icon_from_icon_name(EntryIconPosition::Secondary, Some("x-office-calendar-symbolic"));
+    entry.set_icon_tooltip_text(EntryIconPosition::Secondary, Some("Pick a date"));
+    entry.connect_icon_press(|entry, position, _| {
+        if position == EntryIconPosition::Secondary {
+            open(entry);
+        }
+    });
+}
+
+/// Opens the date picker of the focused entry, if it has one. Run by the pick date action.
+pub fn open_for_focus(window: &ApplicationWindow) {
+    let entry = window.focus().and_then(|widget| widget.downcast::<Entry>().ok());
+    if let Some(entry) = entry.filter(|entry| entry.style_context().has_class(CLASS)) {
+        open(&entry);
+    }
+}
@@ -1,24 +1,20 @@
 use gtk::prelude::*;
-use gtk::Fixed;
+use gtk::Orientation;
 use crate::util;
 use crate::universals;
 
 pub fn view(window: &gtk::ApplicationWindow) {
-    let fixed_container = Fixed::new();
+    let content = gtk::Box::new(Orientation::Vertical, 5);
     util::add_text(
         "DEBUGGING SCREEN",
-        &[40, 20, 50, 20],
-        &[0.1, 0.1],
-        &fixed_container,
+        &[0, 30, 0, 0],
+        &[0.0, 0.5],
+        &content,
         Some("big"),
     );
 
-    let navigation = universals::nav(&universals::Screens::Debug);
-    fixed_container.add(&navigation);
+    let console = universals::debug_console(&[400, 200], &[0, 0, 0, 0], Some("console"));
+    content.pack_start(&console, true, true, 0);
 
-    let console = universals::debug_console(&[1000, 300], &[150, 5, 50, 50], Some("console"));
-    fixed_container.add(&console);
-
-    window.add(&fixed_container);
-    window.show_all();
-}
\ No newline at end of file
+    universals::page(window, &content, &universals::Screens::Debug);
+}
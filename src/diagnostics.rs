@@ -0,0 +1,221 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use chrono::prelude::*;
+use gtk::prelude::*;
+use gtk::{ApplicationWindow, FileChooserAction, FileChooserDialog, ResponseType};
+use serde_json::{json, Map, Value};
+
+use crate::logging::{self, Level};
+use crate::routes::{Navigator, Route};
+use crate::{config, globals};
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// Size of a tar block; headers take one and file contents are padded to a whole number of them.
+const TAR_BLOCK: usize = 512;
+
+/// Writes `value` as a zero-terminated octal number filling `field`, as tar headers store numbers.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+}
+
+/// Appends one file to a tar archive in the ustar format, which every tar tool reads.
+fn append_tar_entry(archive: &mut Vec<u8>, name: &str, content: &[u8], mtime: i64) {
+    let mut header = [0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], content.len() as u64);
+    write_octal(&mut header[136..148], mtime.max(0) as u64);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u64 = header.iter().map(|byte| *byte as u64).sum();
+    write_octal(&mut header[148..155], checksum);
+
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(content);
+    let padding = (TAR_BLOCK - content.len() % TAR_BLOCK) % TAR_BLOCK;
+    archive.extend(std::iter::repeat(0).take(padding));
+}
+
+/// Describes the shape of a JSON value without its data: strings, numbers and booleans become their type,
+/// lists become their length and the shape of their first entry.
+fn shape(value: &Value) -> Value {
+    match value {
+        Value::Null => json!("null"),
+        Value::Bool(_) => json!("bool"),
+        Value::Number(_) => json!("number"),
+        Value::String(_) => json!("string"),
+        Value::Array(items) => json!({
+            "list_length": items.len(),
+            "entry": items.first().map(shape).unwrap_or(Value::Null),
+        }),
+        Value::Object(map) => Value::Object(map.iter().map(|(key, value)| (key.clone(), shape_field(key, value))).collect()),
+    }
+}
+
+/// Like `shape`, but the keys of maps keyed by user data, such as destination names, are replaced by numbers.
+fn shape_field(key: &str, value: &Value) -> Value {
+    match (key, value) {
+        ("destinations", Value::Object(destinations)) => Value::Object(
+            destinations
+                .values()
+                .enumerate()
+                .map(|(i, destination)| (format!("destination {}", i + 1), shape(destination)))
+                .collect(),
+        ),
+        _ => shape(value),
+    }
+}
+
+/// Summarizes the layout of the database file with every value and every name typed by the user left out,
+/// so it can be shared in bug reports. Itineraries are numbered instead of named.
+fn schema_summary(database_json: &str) -> Value {
+    let data: Value = match serde_json::from_str(database_json) {
+        Ok(data) => data,
+        Err(e) => return json!({ "error": format!("database.json could not be parsed: {}", e) }),
+    };
+
+    match data {
+        Value::Object(itineraries) => {
+            let mut summary = Map::new();
+            summary.insert("itinerary_count".to_string(), json!(itineraries.len()));
+            for (i, itinerary) in itineraries.values().enumerate() {
+                summary.insert(format!("itinerary {}", i + 1), shape(itinerary));
+            }
+            Value::Object(summary)
+        }
+        other => shape(&other),
+    }
+}
+
+/// The app version and the system it runs on.
+fn version_info(session_id: i64) -> String {
+    format!(
+        "{} {}\nOS: {} {}\nSession ID: {}\nWritten: {}\n",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        session_id,
+        Local::now().to_rfc3339()
+    )
+}
+
+/// The config file as written by the user, followed by the settings in effect.
+fn config_info() -> String {
+    let path = config::path();
+    let file = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => format!("# No config file at {}, the defaults are used\n", path.display()),
+        Err(e) => format!("# Config file {} could not be read: {}\n", path.display(), e),
+    };
+
+    format!("{}\n# In effect:\n# {:?}\n", file, globals::get_config())
+}
+
+/// Names the route without its parameters, which hold itinerary titles,
+/// e.g. "Create (itinerary set)".
+fn describe_route(route: &Route) -> String {
+    let (name, parameter, set) = match route {
+        Route::Create { itinerary } => ("Create", "itinerary", itinerary.is_some()),
+        Route::Export { itinerary } => ("Export", "itinerary", itinerary.is_some()),
+        // The other routes have no parameters
+        other => return format!("{:?}", other),
+    };
+    if set {
+        format!("{} ({} set)", name, parameter)
+    } else {
+        name.to_string()
+    }
+}
+
+/// The current route, how it was reached and which session storage keys are set. Values are left out.
+fn state_info(navigator: &Navigator, session_keys: &[String], destination_count: usize, draft_count: usize) -> String {
+    let history: Vec<String> = navigator.history().iter().map(describe_route).collect();
+    format!(
+        "Route: {}\nHistory: {}\nSession storage keys: {}\nDestinations in session: {}\nDrafts: {}\n",
+        describe_route(navigator.current()),
+        history.join(" > "),
+        session_keys.join(", "),
+        destination_count,
+        draft_count
+    )
+}
+
+/// Writes a diagnostic bundle to `path`: a tar archive with the log, the app version, the config,
+/// a redacted summary of the database layout and the current route and session storage keys.
+fn write(path: &Path, navigator: &Navigator) -> io::Result<()> {
+    let (session_id, database_json, mut session_keys, destination_count, draft_count) = {
+        let database = globals::get_database();
+        let db_instance = database.lock().unwrap();
+        let database_json = fs::read_to_string(db_instance.get_path()).unwrap_or_else(|e| format!("\"{}\"", e));
+        (
+            db_instance.session_id(),
+            database_json,
+            db_instance.get_session_storage().keys().cloned().collect::<Vec<String>>(),
+            db_instance.session_snapshot().destinations.len(),
+            db_instance.list_drafts().len(),
+        )
+    };
+    session_keys.sort();
+
+    let mtime = Local::now().timestamp();
+    let mut archive = Vec::new();
+    let mut log_lines = globals::get_debug_log().join("\n");
+    log_lines.push('\n');
+    append_tar_entry(&mut archive, "log.txt", log_lines.as_bytes(), mtime);
+    // Earlier runs are only in the log file
+    if let Ok(log_file) = fs::read(logging::file_path()) {
+        append_tar_entry(&mut archive, "log-file.txt", &log_file, mtime);
+    }
+    append_tar_entry(&mut archive, "version.txt", version_info(session_id).as_bytes(), mtime);
+    append_tar_entry(&mut archive, "config.toml", config_info().as_bytes(), mtime);
+    let schema = serde_json::to_string_pretty(&schema_summary(&database_json))?;
+    append_tar_entry(&mut archive, "database-schema.json", schema.as_bytes(), mtime);
+    append_tar_entry(&mut archive, "state.txt", state_info(navigator, &session_keys, destination_count, draft_count).as_bytes(), mtime);
+
+    // Two empty blocks end the archive
+    archive.extend(std::iter::repeat(0).take(TAR_BLOCK * 2));
+    fs::write(path, archive)
+}
+
+/// Asks where to save a diagnostic bundle and writes it there.
+pub fn save(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    let dialog = FileChooserDialog::with_buttons(
+        Some("Save Diagnostic Bundle"),
+        Some(window),
+        FileChooserAction::Save,
+        &[("Cancel", ResponseType::Cancel), ("Save", ResponseType::Accept)],
+    );
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name(&format!("itinerary_planner-diagnostics-{}.tar", Local::now().format("%Y%m%d-%H%M%S")));
+
+    let navigator = navigator.clone();
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            if let Some(path) = dialog.filename() {
+                match write(&path, &navigator.borrow()) {
+                    Ok(()) => log(Level::Info, &format!("Diagnostic bundle saved to {}", path.display())),
+                    Err(e) => log(Level::Error, &format!("Diagnostic bundle could not be saved to {}: {}", path.display(), e)),
+                }
+            }
+        }
+        dialog.close();
+    });
+
+    dialog.show_all();
+}
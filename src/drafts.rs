@@ -0,0 +1,37 @@
+use gtk::prelude::*;
+use gtk::Orientation;
+use crate::util;
+use crate::universals;
+use crate::globals;
+
+/// Name of the window action that switches to a draft, taking the draft's ID.
+pub const SWITCH_ACTION: &str = "switch_draft";
+
+/// Builds and displays the "Drafts" screen, listing every itinerary being edited.
+/// Picking one makes it the draft shown on the CREATE screen.
+pub fn view(window: &gtk::ApplicationWindow) {
+    let content = gtk::Box::new(Orientation::Vertical, 5);
+    let drafts = {
+        let database = globals::get_database();
+        let database_instance = database.lock().unwrap();
+        database_instance.list_drafts()
+    };
+
+    util::add_text("ITINERARY PLANNER", &[0, 50, 0, 0], &[0.0, 0.5], &content, Some("big"));
+    util::add_text("Drafts:", &[0, 20, 0, 0], &[0.0, 0.5], &content, Some("medium"));
+
+    for (i, draft) in drafts.iter().enumerate() {
+        let mut label = format!("{}. {}", i + 1, draft.title);
+        if draft.unsaved {
+            label.push_str(" (unsaved)");
+        }
+        if draft.active {
+            label.push_str(" - editing");
+        }
+        let action = format!("win.{}(uint64 {})", SWITCH_ACTION, draft.id);
+        let button = util::add_text_button_manual(&label, &[5, 5, 0, 0], &[0, 0], Some("option"), Some(&action));
+        content.pack_start(&button, false, false, 0);
+    }
+
+    universals::page(window, &content, &universals::Screens::Drafts);
+}
@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::rc::Rc;
+
+use glib::clone;
+use gtk::prelude::*;
+use gtk::{Label, Orientation};
+use crate::database::Database;
+use crate::logging::Level;
+use crate::routes::{self, Navigator};
+use crate::{bundle, csv_io, globals, pdf, printable, universals, util};
+
+// Enum to differentiate between label and box widgets
+enum Widget {
+    Label(Label),
+    Box(gtk::Box),
+}
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// Formats the EXPORT screen can write, by the name typed into its "Format" field.
+pub const FORMATS: [&str; 5] = ["markdown", "html", "pdf", "bundle", "csv"];
+
+/// Turns an itinerary title into a safe file name stem, e.g. "Japan 2025!" -> "japan-2025".
+pub fn file_stem(title: &str) -> String {
+    let stem: String = title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+
+    let stem = stem
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if stem.is_empty() {
+        "itinerary".to_string()
+    } else {
+        stem
+    }
+}
+
+/// Reads the "Format" field: a comma separated list of formats, or "all".
+fn parse_formats(text: &str) -> Vec<String> {
+    let text = text.trim().to_lowercase();
+    if text.is_empty() || text == "all" {
+        return FORMATS.iter().map(|f| f.to_string()).collect();
+    }
+
+    text.split(',')
+        .map(|f| f.trim().to_string())
+        .filter(|f| !f.is_empty())
+        .collect()
+}
+
+/// Writes a saved itinerary to `folder` in each of the requested formats.
+pub fn write(database: &Database, title: &str, folder: &str, formats: &str, redact_codes: bool) -> Result<String, io::Error> {
+    let plan = match printable::build_plan(database, title) {
+        Some(plan) => plan,
+        None => return Ok("ERROR_NO_ITINERARY".to_string()),
+    };
+
+    let formats = parse_formats(formats);
+    if let Some(unknown) = formats.iter().find(|f| !FORMATS.contains(&f.as_str())) {
+        log(Level::Error, &format!("Unknown export format '{}'", unknown));
+        return Ok("ERROR_UNKNOWN_FORMAT".to_string());
+    }
+
+    let folder = if folder.trim().is_empty() { "." } else { folder.trim() };
+    fs::create_dir_all(folder)?;
+    let stem = Path::new(folder).join(file_stem(title));
+
+    for format in formats {
+        let path = match format.as_str() {
+            "markdown" => {
+                let path = stem.with_extension("md");
+                fs::write(&path, printable::to_markdown(&plan, redact_codes))?;
+                path
+            }
+            "html" => {
+                let path = stem.with_extension("html");
+                fs::write(&path, printable::to_html(&plan, redact_codes))?;
+                path
+            }
+            "pdf" => {
+                let path = stem.with_extension("pdf");
+                let itinerary = database.get_itinerary(title).unwrap_or_default();
+                pdf::write(&plan, &itinerary, &path, redact_codes)?;
+                path
+            }
+            "bundle" => {
+                let path = stem.with_extension(bundle::EXTENSION);
+                bundle::export(database, title, &path)?;
+                path
+            }
+            _ => {
+                // One file per kind, named after the itinerary, e.g. "japan-2025-flights.csv"
+                csv_io::export(database, title, Path::new(folder), &file_stem(title))?;
+                Path::new(folder).to_path_buf()
+            }
+        };
+
+        log(Level::Info, &format!("Exported '{}' to {}", title, path.display()));
+    }
+
+    Ok("SUCCESS".to_string())
+}
+
+/// Builds and displays the "Export Itinerary" screen
+pub fn view(window: &gtk::ApplicationWindow) {
+    let database = globals::get_database();
+
+    // Create main containers
+    let vertical_container = gtk::Box::new(Orientation::Vertical, 5);
+
+    // Create UI elements
+    let title_label = util::add_text_manual("ITINERARY PLANNER", &[0, 50, 0, 0], Some("big"));
+    let subtitle_label = util::add_text_manual("Export Itinerary: ", &[0, 10, 0, 0], Some("medium"));
+    let itinerary_entry = util::create_text_entry_manual("Itinerary: ", &[500, 20], &[0, 0, 30, 0], Some("text_entry"), Some("text_entry_label"));
+    let folder_entry = util::create_text_entry_manual("Folder: ", &[500, 20], &[0, 0, 30, 0], Some("text_entry"), Some("text_entry_label"));
+    let format_entry = util::create_text_entry_manual("Format: ", &[500, 20], &[0, 0, 30, 0], Some("text_entry"), Some("text_entry_label"));
+    let redact_entry = util::create_text_entry_manual("Redact Codes (y/n): ", &[500, 20], &[0, 0, 30, 0], Some("text_entry"), Some("text_entry_label"));
+    let formats_label = util::add_text_manual(&format!("Formats: {} (comma separated) or all", FORMATS.join(", ")), &[15, 0, 30, 0], None);
+
+    // Extract entry fields for further use
+    let ext_itinerary_entry = util::find_entry_in_box(&itinerary_entry);
+    let ext_folder_entry = util::find_entry_in_box(&folder_entry);
+    let ext_format_entry = util::find_entry_in_box(&format_entry);
+    let ext_redact_entry = util::find_entry_in_box(&redact_entry);
+
+    // Load session data into entry fields, with defaults for a first export
+    {
+        let mut db_instance = database.lock().unwrap();
+        let session_storage = db_instance.get_session_storage();
+        let fields = [
+            (&ext_itinerary_entry, "export_itinerary", session_storage.get("title").cloned().unwrap_or_default()),
+            (&ext_folder_entry, "export_folder", ".".to_string()),
+            (&ext_format_entry, "export_format", "all".to_string()),
+            (&ext_redact_entry, "export_redact", "n".to_string()),
+        ];
+
+        for (entry, key, default) in fields.iter() {
+            let value = session_storage.get(*key).cloned().unwrap_or_else(|| default.clone());
+            entry.set_text(&value);
+            db_instance.set_kv_session_storage(key, &value);
+        }
+    }
+
+    // Set up event listeners to store input changes
+    util::on_change(ext_itinerary_entry.clone(), clone!(@weak ext_itinerary_entry, @strong database => move || {
+        let new_text = ext_itinerary_entry.text().to_string();
+        let mut db_instance = database.lock().unwrap();
+        db_instance.set_kv_session_storage("export_itinerary", &new_text);
+    }));
+
+    util::on_change(ext_folder_entry.clone(), clone!(@weak ext_folder_entry, @strong database => move || {
+        let new_text = ext_folder_entry.text().to_string();
+        let mut db_instance = database.lock().unwrap();
+        db_instance.set_kv_session_storage("export_folder", &new_text);
+    }));
+
+    util::on_change(ext_format_entry.clone(), clone!(@weak ext_format_entry, @strong database => move || {
+        let new_text = ext_format_entry.text().to_string();
+        let mut db_instance = database.lock().unwrap();
+        db_instance.set_kv_session_storage("export_format", &new_text);
+    }));
+
+    util::on_change(ext_redact_entry.clone(), clone!(@weak ext_redact_entry, @strong database => move || {
+        let new_text = ext_redact_entry.text().to_string();
+        let mut db_instance = database.lock().unwrap();
+        db_instance.set_kv_session_storage("export_redact", &new_text);
+    }));
+
+    // Group widgets for easier layout management
+    let widgets = vec![
+        Widget::Label(title_label),
+        Widget::Label(subtitle_label),
+        Widget::Box(itinerary_entry),
+        Widget::Box(folder_entry),
+        Widget::Box(format_entry),
+        Widget::Box(redact_entry),
+        Widget::Label(formats_label),
+    ];
+
+    // Add widgets to vertical container
+    for widget in widgets {
+        match widget {
+            Widget::Label(label) => {
+                label.set_xalign(0.0);
+                vertical_container.pack_start(&label, false, false, 0);
+            }
+            Widget::Box(box_widget) => {
+                vertical_container.pack_start(&box_widget, false, false, 0);
+            }
+        }
+    }
+
+    // Attach UI to the window with the navigation bar and display it
+    universals::page(window, &vertical_container, &universals::Screens::Export);
+}
+
+/// Exports the itinerary with the options entered on the EXPORT screen and goes back.
+/// If the export fails the screen stays open with the error shown above the options.
+pub fn confirm(window: &gtk::ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    let result = {
+        let database = globals::get_database();
+        let db_instance = database.lock().unwrap();
+        let session_storage = db_instance.get_session_storage();
+        let field = |key: &str| session_storage.get(key).cloned().unwrap_or_default();
+        let redact_codes = field("export_redact").trim().to_lowercase().starts_with('y');
+
+        write(&db_instance, &field("export_itinerary"), &field("export_folder"), &field("export_format"), redact_codes)
+    };
+
+    match result {
+        Ok(s) if s == "SUCCESS" => {
+            log(Level::Info, "Itinerary exported.");
+            routes::route_back(window, navigator);
+        }
+        Ok(s) => {
+            log(Level::Error, &format!("Export failed: {}", s));
+            universals::show_error(window, "The itinerary could not be exported.", &s);
+        }
+        Err(e) => {
+            log(Level::Error, &format!("Export failed: {}", e));
+            universals::show_error(window, "The itinerary could not be exported.", &e.to_string());
+        }
+    }
+}
@@ -1,20 +1,24 @@
-use gtk::prelude::*;
-use gtk::Fixed;
-use crate::util;
-use crate::universals;
+use crate::forms::{self, FieldSpec, FormSpec, Kind};
+use crate::universals::Screens;
 
-pub fn view(window: &gtk::ApplicationWindow) {
-    let fixed = Fixed::new();
-
-    util::add_text("ITINERARY PLANNER", &[40, 100, 50, 20], &[0.1, 0.1], &fixed, Some("big"));
-
-    util::add_text("Options",                      &[140, 20, 50, 20], &[0.5, 0.5], &fixed, None);
-    util::add_text("Add Flight: ",                &[180, 20, 100, 20], &[0.1, 0.1], &fixed, None);
+/// A flight to add to the saved itinerary being edited by the save flight action.
+const FORM: FormSpec = FormSpec {
+    screen: Screens::Flights,
+    heading: "Add Flight: ",
+    fields: &[
+        FieldSpec::new("Flight Number: ", "flight_number", Kind::Text).required(),
+        FieldSpec::new("From (Airport): ", "flight_from", Kind::AirportCode).required(),
+        FieldSpec::new("To (Airport): ", "flight_to", Kind::AirportCode).required(),
+        FieldSpec::new("Departure Date: ", "flight_departure_date", Kind::Date).required(),
+        FieldSpec::new("Departure Time: ", "flight_departure_time", Kind::Time),
+        FieldSpec::new("Arrival Date: ", "flight_arrival_date", Kind::Date).not_before("flight_departure_date"),
+        FieldSpec::new("Arrival Time: ", "flight_arrival_time", Kind::Time),
+        FieldSpec::new("Confirmation: ", "flight_confirmation", Kind::Text),
+        FieldSpec::new("Notes: ", "flight_notes", Kind::Text),
+    ],
+    options: &[],
+};
 
-
-    let nav = universals::nav(&universals::Screens::Flights);
-    fixed.add(&nav);
-
-    window.add(&fixed);
-    window.show_all();
-}
\ No newline at end of file
+pub fn view(window: &gtk::ApplicationWindow) {
+    forms::view(window, &FORM);
+}
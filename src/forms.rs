@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use glib::clone;
+use gtk::prelude::*;
+use gtk::Orientation;
+
+use crate::actions::Action;
+use crate::universals::{self, Screens};
+use crate::validation::{self, Field, Form, Rule};
+use crate::{datepicker, globals, keymap, util};
+
+/// What a field holds, which decides how it is checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Text,
+    Date,
+    Time,
+    AirportCode,
+    Amount,
+}
+
+/// One entry field of a form screen.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldSpec {
+    pub label: &'static str,
+    /// Session storage key the field is loaded from and written to as it is typed.
+    pub key: &'static str,
+    pub kind: Kind,
+    pub required: bool,
+    /// Key of the date field this date may not be before, e.g. the start date for an end date.
+    pub not_before: Option<&'static str>,
+}
+
+impl FieldSpec {
+    pub const fn new(label: &'static str, key: &'static str, kind: Kind) -> FieldSpec {
+        FieldSpec { label, key, kind, required: false, not_before: None }
+    }
+
+    pub const fn required(self) -> FieldSpec {
+        FieldSpec { required: true, ..self }
+    }
+
+    pub const fn not_before(self, key: &'static str) -> FieldSpec {
+        FieldSpec { not_before: Some(key), ..self }
+    }
+}
+
+/// A screen made of entry fields followed by option buttons. The nav bar comes from the keymap as on every screen.
+pub struct FormSpec {
+    pub screen: Screens,
+    /// Shown under the app title, e.g. "Add Flight: ".
+    pub heading: &'static str,
+    pub fields: &'static [FieldSpec],
+    /// Buttons under the fields, each labelled with its key and the text given, e.g. "Shift + 4 - Flights".
+    pub options: &'static [(Action, &'static str)],
+}
+
+/// The rules a field's text must pass, apart from `not_before`.
+fn kind_rules(spec: &FieldSpec) -> Vec<Rule> {
+    let mut rules = Vec::new();
+    if spec.required {
+        rules.push(Rule::Required);
+    }
+    match spec.kind {
+        Kind::Text => {}
+        Kind::Date => rules.push(Rule::Date),
+        Kind::Time => rules.push(Rule::Time),
+        Kind::AirportCode => rules.push(Rule::AirportCode),
+        Kind::Amount => rules.push(Rule::Amount),
+    }
+    rules
+}
+
+/// The rules a field's text must pass. `fields` are the fields built so far, for `not_before`.
+fn rules(spec: &FieldSpec, fields: &[(FieldSpec, Field)]) -> Vec<Rule> {
+    let mut rules = kind_rules(spec);
+    if let Some(start) = spec.not_before.and_then(|key| fields.iter().find(|(other, _)| other.key == key)) {
+        rules.push(validation::not_before(&start.1.entry));
+    }
+    rules
+}
+
+/// Checks values by session storage key against the form's rules, without a screen, e.g. for a draft
+/// saved from another screen. Returns the first invalid field's label and message.
+pub fn check_values(spec: &FormSpec, values: &HashMap<String, String>) -> Result<(), String> {
+    for field_spec in spec.fields {
+        let mut rules = kind_rules(field_spec);
+        if let Some(start) = field_spec.not_before.and_then(|key| values.get(key)) {
+            rules.push(validation::not_before_text(start));
+        }
+        let text = values.get(field_spec.key).map_or("", |value| value.as_str());
+        validation::check_all(&rules, text).map_err(|message| format!("{}{}", field_spec.label, message))?;
+    }
+    Ok(())
+}
+
+/// Builds and displays a form screen: its fields are filled from session storage, write back to it
+/// as they are typed and are checked before the screen's save action runs.
+pub fn view(window: &gtk::ApplicationWindow, spec: &FormSpec) {
+    let content = gtk::Box::new(Orientation::Vertical, 5);
+    let keymap = keymap::current();
+
+    for (text, margin, class) in [("ITINERARY PLANNER", [0, 50, 0, 0], "big"), (spec.heading, [0, 10, 0, 0], "medium")].iter() {
+        let label = util::add_text_manual(text, margin, Some(*class));
+        label.set_xalign(0.0);
+        content.pack_start(&label, false, false, 0);
+    }
+
+    let mut fields: Vec<(FieldSpec, Field)> = Vec::new();
+    for field_spec in spec.fields {
+        let rules = rules(field_spec, &fields);
+        let field = Field::new(field_spec.label, &[500, 20], &[0, 0, 30, 0], Some("text_entry"), Some("text_entry_label"), rules);
+        if field_spec.kind == Kind::Date {
+            datepicker::attach(&field.entry);
+        }
+        content.pack_start(&field.container, false, false, 0);
+        fields.push((*field_spec, field));
+    }
+
+    // Load session data into entry fields
+    let values: Vec<Option<String>> = {
+        let database = globals::get_database();
+        let db_instance = database.lock().unwrap();
+        let session_storage = db_instance.get_session_storage();
+        fields.iter().map(|(field_spec, _)| session_storage.get(field_spec.key).cloned()).collect()
+    };
+    for ((_, field), value) in fields.iter().zip(values) {
+        if let Some(value) = value {
+            field.entry.set_text(&value);
+        }
+    }
+
+    // Store input changes
+    for (field_spec, field) in fields.iter() {
+        let key = field_spec.key;
+        let entry = &field.entry;
+        util::on_change(entry.clone(), clone!(@weak entry => move || {
+            let database = globals::get_database();
+            let mut db_instance = database.lock().unwrap();
+            db_instance.set_kv_session_storage(key, &entry.text());
+        }));
+    }
+
+    for (action, label) in spec.options {
+        let text = match keymap.key_for(spec.screen, *action) {
+            Some(key) => format!("{} - {}", key, label),
+            None => label.to_string(),
+        };
+        let button = util::add_text_button_manual(&text, &[15, 0, 30, 0], &[0, 0], Some("option"), Some(&action.detailed_name()));
+        content.pack_start(&button, false, false, 0);
+    }
+
+    // The screen's save action only runs once these fields are valid
+    let form = Form::new();
+    for (_, field) in fields.iter() {
+        form.add(field);
+    }
+    validation::set_current_form(&form);
+
+    // Attach UI to the window with the navigation bar and display it
+    universals::page(window, &content, &spec.screen);
+}
@@ -1,25 +1,71 @@
 use lazy_static::lazy_static;
-use std::sync::{Arc, Mutex};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use crate::config::Config;
 use crate::database;
+use crate::history::History;
+use crate::logging::{self, Level, Record};
 
 #[allow(dead_code)]
 const DEBUG: bool = true;
 
+/// Number of log records kept in memory. Older ones are only in the log file.
+pub const MAX_LOG_RECORDS: usize = 2000;
+
 lazy_static! {
-    static ref DEBUG_LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
-    static ref DATABASE: Arc<Mutex<database::Database>> = Arc::new(Mutex::new(database::Database::new("database.json".to_string())));
+    static ref DEBUG_LOG: Mutex<VecDeque<Record>> = Mutex::new(VecDeque::with_capacity(MAX_LOG_RECORDS));
+    static ref CONFIG: Mutex<Config> = Mutex::new(Config::default());
+    static ref HISTORY: Mutex<History> = Mutex::new(History::new());
+    static ref DATABASE_PATH: Mutex<String> = Mutex::new("database.json".to_string());
+    static ref DATABASE: Arc<Mutex<database::Database>> = Arc::new(Mutex::new(database::Database::new(DATABASE_PATH.lock().unwrap().clone())));
+}
+
+/// Sets the database file to use. Only takes effect before the database is first accessed.
+pub fn set_database_path(path: &str) {
+    *DATABASE_PATH.lock().unwrap() = path.to_string();
+}
+
+/// Logs a message from `module`, usually `module_path!()`, to the in-memory log and the log file.
+pub fn add_to_debug_log(level: Level, module: &str, message: &str) {
+    let mut record = Record::new(level, module, message);
+
+    // Written while holding the log, so records reach the file in order
+    let mut records = DEBUG_LOG.lock().unwrap();
+    record.seq = records.back().map_or(1, |last| last.seq + 1);
+    logging::write_to_file(&record);
+
+    if records.len() >= MAX_LOG_RECORDS {
+        records.pop_front();
+    }
+    records.push_back(record);
 }
 
-pub fn add_to_debug_log(value: &str) {
-    let val: String = value.to_string();
-    let mut vec = DEBUG_LOG.lock().unwrap();
-    vec.push(val);
+/// Returns the log records after the one numbered `seq`, oldest first. 0 returns all of them.
+pub fn get_log_records_since(seq: u64) -> Vec<Record> {
+    DEBUG_LOG.lock().unwrap().iter().filter(|record| record.seq > seq).cloned().collect()
 }
 
+/// Returns the log records still in memory as formatted lines, oldest first.
 pub fn get_debug_log() -> Vec<String> {
-    DEBUG_LOG.lock().unwrap().clone()
+    DEBUG_LOG.lock().unwrap().iter().map(|record| record.line()).collect()
 }
 
 pub fn get_database() -> Arc<Mutex<database::Database>> {
     Arc::clone(&DATABASE)
-}
\ No newline at end of file
+}
+
+/// Returns the undo history of this session.
+/// Don't lock the database while holding it; the database locks it when recording edits.
+pub fn get_history() -> MutexGuard<'static, History> {
+    HISTORY.lock().unwrap()
+}
+
+/// Returns a copy of the current user configuration.
+pub fn get_config() -> Config {
+    CONFIG.lock().unwrap().clone()
+}
+
+/// Replaces the current user configuration, e.g. after reloading the config file.
+pub fn set_config(config: Config) {
+    *CONFIG.lock().unwrap() = config;
+}
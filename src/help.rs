@@ -0,0 +1,79 @@
+use gtk::prelude::*;
+use gtk::{Align, ApplicationWindow, Grid, WindowPosition, WindowType};
+
+use crate::actions::Action;
+use crate::keymap::{self, Chord};
+use crate::universals::Screens;
+use crate::util;
+
+/// Screen names as shown in the overlay title.
+fn screen_title(screen: Screens) -> &'static str {
+    match screen {
+        Screens::Home => "Home",
+        Screens::Create => "Create Itinerary",
+        Screens::Debug => "Debug",
+        Screens::Destinations => "Destinations",
+        Screens::Flights => "Flights",
+        Screens::Places => "Places",
+        Screens::Open => "Saved Itineraries",
+        Screens::Import => "Import",
+        Screens::Export => "Export",
+        Screens::Drafts => "Drafts",
+    }
+}
+
+/// Shows every shortcut bound on the screen, read from the keymap registry.
+/// The overlay closes on ESC or the help key, without passing the key on to the screen.
+pub fn show(window: &ApplicationWindow, screen: Screens) {
+    let keymap = keymap::current();
+
+    let overlay = gtk::Window::new(WindowType::Toplevel);
+    overlay.set_transient_for(Some(window));
+    overlay.set_modal(true);
+    overlay.set_decorated(false);
+    overlay.set_position(WindowPosition::CenterOnParent);
+    overlay.style_context().add_class("help-overlay");
+
+    let grid = Grid::new();
+    grid.set_row_spacing(8);
+    grid.set_column_spacing(30);
+    grid.set_margin_top(30);
+    grid.set_margin_bottom(30);
+    grid.set_margin_start(40);
+    grid.set_margin_end(40);
+
+    let title = util::add_text_manual(&format!("Keyboard Shortcuts: {}", screen_title(screen)), &[0, 15, 0, 0], Some("medium"));
+    title.set_halign(Align::Start);
+    grid.attach(&title, 0, 0, 2, 1);
+
+    let mut row = 1;
+    for binding in keymap.bindings_for(screen) {
+        let key = util::add_text_manual(&binding.chord.display(), &[0, 0, 0, 0], Some("help-key"));
+        key.set_halign(Align::End);
+        let description = util::add_text_manual(&util::capitalize(binding.action.description()), &[0, 0, 0, 0], None);
+        description.set_halign(Align::Start);
+
+        grid.attach(&key, 0, row, 1, 1);
+        grid.attach(&description, 1, row, 1, 1);
+        row += 1;
+    }
+
+    let close_hint = match keymap.key_for(screen, Action::Help) {
+        Some(key) => format!("Press {} or ESC to close", key),
+        None => "Press ESC to close".to_string(),
+    };
+    let footer = util::add_text_manual(&close_hint, &[15, 0, 0, 0], Some("navbar-item"));
+    footer.set_halign(Align::Start);
+    grid.attach(&footer, 0, row, 2, 1);
+
+    overlay.connect_key_press_event(move |overlay, event| {
+        let chord = Chord::from_event(event);
+        if chord.key == "escape" || keymap.action_for(screen, &chord) == Some(Action::Help) {
+            overlay.close();
+        }
+        Inhibit(true)
+    });
+
+    overlay.add(&grid);
+    overlay.show_all();
+}
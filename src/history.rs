@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Number of edits kept for undo. Older edits are dropped.
+const MAX_EDITS: usize = 200;
+
+/// The itinerary being edited, as held in session storage.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Session {
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+    #[serde(default)]
+    pub destinations: HashMap<String, HashMap<String, String>>,
+}
+
+impl Session {
+    /// Whether nothing has been typed, i.e. there is nothing worth keeping.
+    pub fn is_empty(&self) -> bool {
+        self.fields.values().all(|value| value.trim().is_empty()) && self.destinations.is_empty()
+    }
+}
+
+/// A reversible change, stored as the state before and after it.
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// A draft changed. `draft` is the draft's ID and `key` names the field when a single field was
+    /// typed into, so that typing into the same field again extends the edit instead of adding one per keystroke.
+    Session { draft: u64, key: Option<String>, before: Session, after: Session },
+    /// The database file was rewritten. Holds the whole file before and after.
+    Database { before: String, after: String },
+    /// Edits made by one command, e.g. saving an itinerary, undone and redone together.
+    Group(Vec<Edit>),
+}
+
+/// Undo and redo stacks for the session. Kept across screen changes.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Edits collected since `begin_group`, if a group is open.
+    group: Option<Vec<Edit>>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+
+    /// Records a new edit. Anything undone before it can no longer be redone.
+    pub fn record(&mut self, edit: Edit) {
+        if let Some(group) = self.group.as_mut() {
+            group.push(edit);
+            return;
+        }
+
+        self.redo.clear();
+
+        if let Edit::Session { draft, key: Some(key), after, .. } = &edit {
+            if let Some(Edit::Session { draft: last_draft, key: Some(last_key), after: last_after, .. }) = self.undo.last_mut() {
+                if draft == last_draft && key == last_key {
+                    *last_after = after.clone();
+                    return;
+                }
+            }
+        }
+
+        self.undo.push(edit);
+        if self.undo.len() > MAX_EDITS {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Starts collecting edits into one group, until `end_group`.
+    pub fn begin_group(&mut self) {
+        if self.group.is_none() {
+            self.group = Some(Vec::new());
+        }
+    }
+
+    /// Records the edits collected since `begin_group` as a single edit.
+    pub fn end_group(&mut self) {
+        if let Some(edits) = self.group.take() {
+            match edits.len() {
+                0 => {}
+                1 => self.record(edits.into_iter().next().expect("group has one edit")),
+                _ => self.record(Edit::Group(edits)),
+            }
+        }
+    }
+
+    /// Takes the most recent edit to undo it.
+    pub fn take_undo(&mut self) -> Option<Edit> {
+        self.undo.pop()
+    }
+
+    /// Takes the most recently undone edit to redo it.
+    pub fn take_redo(&mut self) -> Option<Edit> {
+        self.redo.pop()
+    }
+
+    /// Pushes an edit onto the undo stack as is, e.g. once redone or after a failed undo.
+    pub fn push_undo(&mut self, edit: Edit) {
+        self.undo.push(edit);
+    }
+
+    /// Pushes an edit onto the redo stack as is, e.g. once undone or after a failed redo.
+    pub fn push_redo(&mut self, edit: Edit) {
+        self.redo.push(edit);
+    }
+}
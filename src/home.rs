@@ -1,18 +1,28 @@
 use gtk::prelude::*;
-use gtk::Fixed;
+use gtk::Orientation;
 use crate::util;
-use crate::universals;
+use crate::universals::{self, Screens};
+use crate::actions::Action;
+use crate::keymap;
 
 pub fn view(window: &gtk::ApplicationWindow) {
-    let fixed = Fixed::new();
-    util::add_text("ITINERARY PLANNER",        &[40, 20, 50, 20], &[0.1, 0.1], &fixed, Some("big"));
-    util::add_text("Shift + 1 - Create new itinerary", &[140, 20, 80, 20], &[0.1, 0.1], &fixed, None);
-    util::add_text("Shift + 2 - Open itinerary",       &[180, 20, 80, 20], &[0.1, 0.1], &fixed, None);
-    util::add_text("Shift + F - Debug",       &[220, 20, 80, 20], &[0.1, 0.1], &fixed, None);
+    let content = gtk::Box::new(Orientation::Vertical, 5);
+    util::add_text("ITINERARY PLANNER", &[0, 50, 0, 0], &[0.0, 0.5], &content, Some("big"));
+    let keymap = keymap::current();
+    let options = [
+        (Action::NewItinerary, "Create new itinerary"),
+        (Action::OpenItineraries, "Open itinerary"),
+        (Action::Export, "Export itinerary"),
+        (Action::Drafts, "Drafts"),
+        (Action::Debug, "Debug"),
+        (Action::ReloadConfig, "Reload config"),
+    ];
+    for (action, label) in options.iter() {
+        if let Some(key) = keymap.key_for(Screens::Home, *action) {
+            let button = util::add_text_button_manual(&format!("{} - {}", key, label), &[5, 5, 0, 0], &[0, 0], Some("option"), Some(&action.detailed_name()));
+            content.pack_start(&button, false, false, 0);
+        }
+    }
 
-    let nav = universals::nav(&universals::Screens::Home);
-    fixed.add(&nav);
-
-    window.add(&fixed);
-    window.show_all();
-}
\ No newline at end of file
+    universals::page(window, &content, &Screens::Home);
+}
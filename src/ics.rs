@@ -0,0 +1,307 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::database::{Booking, BookingKind, Database};
+use crate::logging::Level;
+use crate::{globals, util};
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// Represents a single VEVENT read from an iCalendar file.
+#[derive(Debug, Default, Clone)]
+pub struct Event {
+    pub uid: String,
+    pub summary: String,
+    pub location: String,
+    pub description: String,
+    pub categories: String,
+    pub start: String,
+    pub end: String,
+}
+
+/// A booking that an import would create, or skip because it was already imported.
+#[derive(Debug, Clone)]
+pub struct PreviewEntry {
+    pub kind: BookingKind,
+    pub booking: Booking,
+    pub skip: bool,
+}
+
+/// Joins folded lines back together. Continuation lines start with a space or a tab.
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(&line[1..]);
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+
+    lines
+}
+
+/// Reverts the escaping iCalendar applies to text values.
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => result.push('\n'),
+                Some(other) => result.push(other),
+                None => {}
+            }
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Converts an iCalendar date or date-time into the format used across the app.
+/// UTC times, ending in "Z", are converted to local time. Values that cannot be parsed are kept as they are.
+fn format_date(value: &str) -> String {
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+
+    if let Ok(mut date_time) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        if utc {
+            date_time = Utc.from_utc_datetime(&date_time).with_timezone(&Local).naive_local();
+        }
+        return format!("{} {}", util::format_date(date_time.date()), date_time.format("%H:%M"));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return util::format_date(date);
+    }
+
+    value.to_string()
+}
+
+/// Parses the VEVENTs out of iCalendar content. Other components are ignored.
+pub fn parse(content: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Event> = None;
+
+    for line in unfold(content) {
+        if line == "BEGIN:VEVENT" {
+            current = Some(Event::default());
+            continue;
+        }
+        if line == "END:VEVENT" {
+            if let Some(event) = current.take() {
+                events.push(event);
+            }
+            continue;
+        }
+
+        let event = match current.as_mut() {
+            Some(event) => event,
+            None => continue,
+        };
+
+        // Property parameters (e.g. DTSTART;TZID=Europe/Paris) are dropped, only the name matters.
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.split(';').next().unwrap_or(name), value),
+            None => continue,
+        };
+
+        match name {
+            "UID" => event.uid = value.to_string(),
+            "SUMMARY" => event.summary = unescape(value),
+            "LOCATION" => event.location = unescape(value),
+            "DESCRIPTION" => event.description = unescape(value),
+            "CATEGORIES" => event.categories = unescape(value),
+            "DTSTART" => event.start = format_date(value),
+            "DTEND" => event.end = format_date(value),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+/// Guesses which kind of booking an event describes from its summary, categories and description.
+fn classify(event: &Event) -> BookingKind {
+    let text = format!("{} {} {}", event.summary, event.categories, event.description).to_lowercase();
+
+    let flight_words = ["flight", "airline", "boarding", "departure gate"];
+    let accommodation_words = ["hotel", "check-in", "check in", "accommodation", "hostel", "airbnb", "stay at", "lodging"];
+
+    if flight_words.iter().any(|word| text.contains(word)) {
+        BookingKind::Flight
+    } else if accommodation_words.iter().any(|word| text.contains(word)) {
+        BookingKind::Accommodation
+    } else {
+        BookingKind::Travel
+    }
+}
+
+/// Looks for a confirmation code in the event description, e.g. "Confirmation: ABC123".
+/// Markers only count at the start of a word, so "pnr" in "Spnr" is not one.
+fn find_confirmation(description: &str) -> String {
+    let markers = ["confirmation number", "confirmation", "booking reference", "reservation number", "pnr"];
+
+    for line in description.lines() {
+        let lower = line.to_ascii_lowercase();
+        for marker in markers.iter() {
+            let starts_word = |index: &usize| !lower[..*index].chars().last().map_or(false, |c| c.is_alphanumeric());
+            if let Some(index) = lower.match_indices(marker).map(|(index, _)| index).find(starts_word) {
+                let rest = &line[index + marker.len()..];
+                let code = rest.trim_start_matches(|c: char| c == ':' || c == '#' || c.is_whitespace());
+                if let Some(code) = code.split_whitespace().next() {
+                    return code.to_string();
+                }
+            }
+        }
+    }
+
+    String::new()
+}
+
+/// Maps an event onto the booking it would be imported as.
+fn to_booking(event: &Event) -> (BookingKind, Booking) {
+    let booking = Booking {
+        uid: event.uid.clone(),
+        title: event.summary.clone(),
+        start: event.start.clone(),
+        end: event.end.clone(),
+        location: event.location.clone(),
+        confirmation: find_confirmation(&event.description),
+        notes: event.description.clone(),
+    };
+
+    (classify(event), booking)
+}
+
+/// Reads an .ics file and lists what importing it into the given itinerary would create.
+/// Events whose UID is already in the itinerary, or earlier in the same file, are marked as skipped.
+pub fn preview(database: &Database, path: &str, itinerary: &str) -> Result<Vec<PreviewEntry>, io::Error> {
+    let content = fs::read_to_string(path)?;
+    let mut seen: HashSet<String> = database.booking_uids(itinerary);
+
+    let entries = parse(&content)
+        .iter()
+        .map(|event| {
+            let (kind, booking) = to_booking(event);
+            let skip = !booking.uid.is_empty() && !seen.insert(booking.uid.clone());
+            PreviewEntry { kind, booking, skip }
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// Imports the events of an .ics file into a saved itinerary, skipping already imported ones.
+pub fn import(database: &Database, path: &str, itinerary: &str) -> Result<String, io::Error> {
+    if database.get_itinerary(itinerary).is_none() {
+        return Ok("ERROR_NO_ITINERARY".to_string());
+    }
+
+    let bookings: Vec<(BookingKind, Booking)> = preview(database, path, itinerary)?
+        .into_iter()
+        .filter(|entry| !entry.skip)
+        .map(|entry| (entry.kind, entry.booking))
+        .collect();
+
+    if bookings.is_empty() {
+        log(Level::Info, &format!("Nothing new to import from {}", path));
+        return Ok("NOTHING_TO_IMPORT".to_string());
+    }
+
+    log(Level::Info, &format!("Importing {} booking(s) from {} into '{}'", bookings.len(), path, itinerary));
+    database.add_bookings(itinerary, &bookings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unfold_joins_continuation_lines() {
+        let content = "DESCRIPTION:Flight to\r\n  Paris\r\n\tgate 12\r\nSUMMARY:Trip\r\n";
+        assert_eq!(unfold(content), vec!["DESCRIPTION:Flight to Paris gate 12", "SUMMARY:Trip"]);
+    }
+
+    #[test]
+    fn unfold_keeps_a_leading_continuation_line() {
+        assert_eq!(unfold(" orphan\nSUMMARY:Trip"), vec![" orphan", "SUMMARY:Trip"]);
+    }
+
+    #[test]
+    fn unescape_reverts_text_escaping() {
+        assert_eq!(unescape(r"Gate 12\, Terminal 2\; seat 4A\nBring passport\\"), "Gate 12, Terminal 2; seat 4A\nBring passport\\");
+        assert_eq!(unescape(r"Line\NBreak"), "Line\nBreak");
+        // A trailing backslash escapes nothing and is dropped
+        assert_eq!(unescape("Trailing\\"), "Trailing");
+    }
+
+    #[test]
+    fn parse_reads_events_only() {
+        let content = "BEGIN:VCALENDAR\r\n\
+            SUMMARY:Calendar name\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:abc-1\r\n\
+            SUMMARY:Flight BA123\\, London\r\n\
+            DTSTART;TZID=Europe/London:20250312T101500\r\n\
+            DTEND:20250312\r\n\
+            LOCATION:Heathrow\r\n\
+            DESCRIPTION:Confirmation: XY12\r\n \
+            Z3\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let events = parse(content);
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.uid, "abc-1");
+        assert_eq!(event.summary, "Flight BA123, London");
+        assert_eq!(event.location, "Heathrow");
+        assert_eq!(event.description, "Confirmation: XY12Z3");
+        let date = NaiveDate::from_ymd_opt(2025, 3, 12).unwrap();
+        assert_eq!(event.start, format!("{} 10:15", util::format_date(date)));
+        assert_eq!(event.end, util::format_date(date));
+    }
+
+    #[test]
+    fn parse_drops_an_unterminated_event() {
+        assert!(parse("BEGIN:VEVENT\nUID:abc-1\nSUMMARY:Lost").is_empty());
+    }
+
+    #[test]
+    fn find_confirmation_reads_the_code_after_a_marker() {
+        assert_eq!(find_confirmation("Hotel stay\nBooking reference # QW-991 (paid)"), "QW-991");
+        assert_eq!(find_confirmation("No code here"), "");
+    }
+
+    #[test]
+    fn find_confirmation_skips_markers_inside_words() {
+        assert_eq!(find_confirmation("Snapnr 4 guests, PNR: K7F2QX"), "K7F2QX");
+        assert_eq!(find_confirmation("Unconfirmation pending"), "");
+    }
+
+    #[test]
+    fn format_date_converts_utc_to_local_time() {
+        let utc = NaiveDate::from_ymd_opt(2025, 3, 12).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        let local = Local.from_utc_datetime(&utc);
+        let expected = format!("{} {}", util::format_date(local.naive_local().date()), local.format("%H:%M"));
+        assert_eq!(format_date("20250312T090000Z"), expected);
+
+        let date = NaiveDate::from_ymd_opt(2025, 3, 12).unwrap();
+        assert_eq!(format_date("20250312T090000"), format!("{} 09:00", util::format_date(date)));
+    }
+}
@@ -0,0 +1,237 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use glib::clone;
+use gtk::prelude::*;
+use gtk::{Label, Orientation};
+use crate::bundle::{self, ConflictPolicy};
+use crate::logging::Level;
+use crate::routes::{self, Navigator};
+use crate::{csv_io, globals, ics, universals, util};
+
+// Enum to differentiate between label and box widgets
+enum Widget {
+    Label(Label),
+    Box(gtk::Box),
+}
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// Whether the file at `path` is an itinerary bundle rather than an .ics file.
+fn is_bundle(path: &str) -> bool {
+    path.trim().to_lowercase().ends_with(&format!(".{}", bundle::EXTENSION))
+}
+
+/// Whether the file at `path` is a CSV export of flights, travel, lodging or expenses.
+fn is_csv(path: &str) -> bool {
+    path.trim().to_lowercase().ends_with(".csv")
+}
+
+/// Rebuilds the preview list from the itinerary and file currently entered in session storage.
+fn refresh_preview(preview_container: &gtk::Box) {
+    for child in preview_container.children() {
+        preview_container.remove(&child);
+    }
+
+    let database = globals::get_database();
+    let db_instance = database.lock().unwrap();
+    let session_storage = db_instance.get_session_storage();
+    let itinerary = session_storage.get("import_itinerary").cloned().unwrap_or_default();
+    let path = session_storage.get("import_path").cloned().unwrap_or_default();
+    let policy = ConflictPolicy::parse(session_storage.get("import_conflict").map(|s| s.as_str()).unwrap_or(""));
+
+    let mut lines: Vec<String> = Vec::new();
+    if is_bundle(&path) {
+        // Bundles carry their own itinerary, so the itinerary field is not used
+        match bundle::preview(&db_instance, &path) {
+            Ok(merge) => {
+                lines.push(format!("Itinerary '{}' with {} attachment(s).", merge.title, merge.attachment_count));
+                match (merge.conflict, policy) {
+                    (None, _) => lines.push("[NEW ] It will be added as a new itinerary.".to_string()),
+                    (Some(existing), ConflictPolicy::Skip) => lines.push(format!("[SKIP] Conflicts with '{}'.", existing)),
+                    (Some(existing), ConflictPolicy::Overwrite) => lines.push(format!("[OVERWRITE] Replaces '{}'.", existing)),
+                    (Some(existing), ConflictPolicy::Rename) => lines.push(format!("[RENAME] Conflicts with '{}', a renamed copy will be added.", existing)),
+                }
+            }
+            Err(e) => lines.push(format!("Could not read '{}': {}", path, e)),
+        }
+    } else if itinerary.is_empty() || path.is_empty() {
+        lines.push("Enter an itinerary and an .ics or .csv file to preview the import.".to_string());
+    } else if db_instance.get_itinerary(&itinerary).is_none() {
+        lines.push(format!("No saved itinerary named '{}'.", itinerary));
+    } else if is_csv(&path) {
+        match csv_io::check(&db_instance, &itinerary, &path) {
+            Ok(report) => {
+                if let Some(kind) = report.kind {
+                    lines.push(format!(
+                        "{} file: {} valid row(s), {} already imported, {} invalid.",
+                        kind.file_suffix(),
+                        report.valid_rows,
+                        report.duplicate_rows,
+                        report.errors.len()
+                    ));
+                }
+                for (line, reason) in report.errors {
+                    lines.push(format!("[ERROR] Line {}: {}", line, reason));
+                }
+            }
+            Err(e) => lines.push(format!("Could not read '{}': {}", path, e)),
+        }
+    } else {
+        match ics::preview(&db_instance, &path, &itinerary) {
+            Ok(entries) if entries.is_empty() => lines.push("No events found in this file.".to_string()),
+            Ok(entries) => {
+                for entry in entries {
+                    let status = if entry.skip { "SKIP" } else { "NEW " };
+                    lines.push(format!(
+                        "[{}] {}: {} ({} - {})",
+                        status,
+                        entry.kind.label(),
+                        entry.booking.title,
+                        entry.booking.start,
+                        entry.booking.end
+                    ));
+                }
+            }
+            Err(e) => lines.push(format!("Could not read '{}': {}", path, e)),
+        }
+    }
+
+    for line in lines {
+        let label = util::add_text_manual(&line, &[0, 0, 30, 0], None);
+        label.set_xalign(0.0);
+        preview_container.pack_start(&label, false, false, 0);
+    }
+    preview_container.show_all();
+}
+
+/// Builds and displays the "Import Bookings" screen
+pub fn view(window: &gtk::ApplicationWindow) {
+    let database = globals::get_database();
+
+    // Create main containers
+    let vertical_container = gtk::Box::new(Orientation::Vertical, 5);
+    let preview_container = gtk::Box::new(Orientation::Vertical, 2);
+
+    // Create UI elements
+    let title_label = util::add_text_manual("ITINERARY PLANNER", &[0, 50, 0, 0], Some("big"));
+    let subtitle_label = util::add_text_manual("Import Bookings (.ics, .csv) or Itinerary Bundle (.itinerary): ", &[0, 10, 0, 0], Some("medium"));
+    let itinerary_entry = util::create_text_entry_manual("Itinerary: ", &[500, 20], &[0, 0, 30, 0], Some("text_entry"), Some("text_entry_label"));
+    let path_entry = util::create_text_entry_manual("File: ", &[500, 20], &[0, 0, 30, 0], Some("text_entry"), Some("text_entry_label"));
+    let conflict_entry = util::create_text_entry_manual("On Conflict: ", &[500, 20], &[0, 0, 30, 0], Some("text_entry"), Some("text_entry_label"));
+    let conflict_label = util::add_text_manual("On Conflict (bundles only): skip, overwrite or rename", &[15, 0, 30, 0], None);
+    let preview_label = util::add_text_manual("Preview: ", &[15, 5, 0, 0], Some("medium"));
+
+    // Extract entry fields for further use
+    let ext_itinerary_entry = util::find_entry_in_box(&itinerary_entry);
+    let ext_path_entry = util::find_entry_in_box(&path_entry);
+    let ext_conflict_entry = util::find_entry_in_box(&conflict_entry);
+
+    // Load session data into entry fields, defaulting to the itinerary being edited
+    {
+        let db_instance = database.lock().unwrap();
+        let session_storage = db_instance.get_session_storage();
+        if let Some(itinerary) = session_storage.get("import_itinerary").or_else(|| session_storage.get("title")) {
+            ext_itinerary_entry.set_text(itinerary);
+        }
+        if let Some(path) = session_storage.get("import_path") {
+            ext_path_entry.set_text(path);
+        }
+        ext_conflict_entry.set_text(session_storage.get("import_conflict").map(|s| s.as_str()).unwrap_or("skip"));
+    }
+
+    // Set up event listeners to store input changes and refresh the preview
+    util::on_change(ext_itinerary_entry.clone(), clone!(@weak ext_itinerary_entry, @weak preview_container, @strong database => move || {
+        let new_text = ext_itinerary_entry.text().to_string();
+        database.lock().unwrap().set_kv_session_storage("import_itinerary", &new_text);
+        refresh_preview(&preview_container);
+    }));
+
+    util::on_change(ext_path_entry.clone(), clone!(@weak ext_path_entry, @weak preview_container, @strong database => move || {
+        let new_text = ext_path_entry.text().to_string();
+        database.lock().unwrap().set_kv_session_storage("import_path", &new_text);
+        refresh_preview(&preview_container);
+    }));
+
+    util::on_change(ext_conflict_entry.clone(), clone!(@weak ext_conflict_entry, @weak preview_container, @strong database => move || {
+        let new_text = ext_conflict_entry.text().to_string();
+        database.lock().unwrap().set_kv_session_storage("import_conflict", &new_text);
+        refresh_preview(&preview_container);
+    }));
+
+    // Store the prefilled values so the preview and the import see the same ones
+    {
+        let mut db_instance = database.lock().unwrap();
+        db_instance.set_kv_session_storage("import_itinerary", &ext_itinerary_entry.text());
+        db_instance.set_kv_session_storage("import_conflict", &ext_conflict_entry.text());
+    }
+
+    // Group widgets for easier layout management
+    let widgets = vec![
+        Widget::Label(title_label),
+        Widget::Label(subtitle_label),
+        Widget::Box(itinerary_entry),
+        Widget::Box(path_entry),
+        Widget::Box(conflict_entry),
+        Widget::Label(conflict_label),
+        Widget::Label(preview_label),
+        Widget::Box(preview_container.clone()),
+    ];
+
+    // Add widgets to vertical container
+    for widget in widgets {
+        match widget {
+            Widget::Label(label) => {
+                label.set_xalign(0.0);
+                vertical_container.pack_start(&label, false, false, 0);
+            }
+            Widget::Box(box_widget) => {
+                vertical_container.pack_start(&box_widget, false, false, 0);
+            }
+        }
+    }
+
+    // Attach UI to the window with the navigation bar and display it
+    universals::page(window, &vertical_container, &universals::Screens::Import);
+
+    refresh_preview(&preview_container);
+}
+
+/// Imports the previewed bookings, expenses or bundle and returns to the CREATE screen.
+/// If the import fails the screen stays open with the error shown above the preview.
+pub fn confirm(window: &gtk::ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    let result = {
+        let database = globals::get_database();
+        let db_instance = database.lock().unwrap();
+        let session_storage = db_instance.get_session_storage();
+        let itinerary = session_storage.get("import_itinerary").cloned().unwrap_or_default();
+        let path = session_storage.get("import_path").cloned().unwrap_or_default();
+
+        if is_bundle(&path) {
+            let policy = ConflictPolicy::parse(session_storage.get("import_conflict").map(|s| s.as_str()).unwrap_or(""));
+            bundle::import(&db_instance, &path, policy)
+        } else if is_csv(&path) {
+            csv_io::import(&db_instance, &itinerary, &path).map(|(status, _)| status)
+        } else {
+            ics::import(&db_instance, &path, &itinerary)
+        }
+    };
+
+    match result {
+        Ok(s) if s == "SUCCESS" || s == "NOTHING_TO_IMPORT" || s == "SKIPPED_CONFLICT" => {
+            log(Level::Info, &format!("Import finished: {}", s));
+            routes::route_back(window, navigator);
+        }
+        Ok(s) => {
+            log(Level::Error, &format!("Import failed: {}", s));
+            universals::show_error(window, "Nothing was imported.", &s);
+        }
+        Err(e) => {
+            log(Level::Error, &format!("Import failed: {}", e));
+            universals::show_error(window, "Nothing was imported.", &e.to_string());
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+
+use gtk::gdk::{EventKey, ModifierType};
+
+use crate::actions::Action;
+use crate::globals;
+use crate::universals::Screens;
+
+/// Punctuation keys by GDK key name and the character they type.
+/// Shift is ignored for these, since it is needed to type many of them in the first place.
+const PUNCTUATION: [(&str, char); 19] = [
+    ("question", '?'),
+    ("slash", '/'),
+    ("backslash", '\\'),
+    ("comma", ','),
+    ("period", '.'),
+    ("colon", ':'),
+    ("semicolon", ';'),
+    ("plus", '+'),
+    ("minus", '-'),
+    ("equal", '='),
+    ("underscore", '_'),
+    ("apostrophe", '\''),
+    ("quotedbl", '"'),
+    ("grave", '`'),
+    ("asciitilde", '~'),
+    ("bracketleft", '['),
+    ("bracketright", ']'),
+    ("less", '<'),
+    ("greater", '>'),
+];
+
+/// A key together with the modifiers held down, e.g. Shift + 1.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// Lowercase GDK key name, e.g. "1", "f", "escape", "question".
+    pub key: String,
+}
+
+impl Chord {
+    /// Parses a chord written as in the config file, e.g. "Shift+1", "Ctrl+Shift+F", "Escape" or "?".
+    pub fn parse(text: &str) -> Result<Chord, String> {
+        let text = text.trim();
+        // "Ctrl++" binds the plus key itself
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => match text.rfind('+') {
+                Some(index) if index + 1 < text.len() => (&text[..index], &text[index + 1..]),
+                _ => ("", text),
+            },
+        };
+
+        let mut chord = Chord { ctrl: false, shift: false, alt: false, key: String::new() };
+        for modifier in modifiers.split('+').map(|m| m.trim()).filter(|m| !m.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "shift" => chord.shift = true,
+                "alt" => chord.alt = true,
+                other => return Err(format!("unknown modifier '{}' in '{}'", other, text)),
+            }
+        }
+
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("missing key in '{}'", text));
+        }
+
+        let mut chars = key.chars();
+        chord.key = match (chars.next(), chars.next()) {
+            (Some(c), None) => match PUNCTUATION.iter().find(|(_, symbol)| *symbol == c) {
+                Some((name, _)) => name.to_string(),
+                None if c.is_ascii_alphanumeric() => c.to_ascii_lowercase().to_string(),
+                None => return Err(format!("unsupported key '{}' in '{}'", key, text)),
+            },
+            _ => match key.to_lowercase().as_str() {
+                "esc" => "escape".to_string(),
+                "enter" => "return".to_string(),
+                name => name.to_string(),
+            },
+        };
+
+        if PUNCTUATION.iter().any(|(name, _)| *name == chord.key) {
+            chord.shift = false;
+        }
+
+        Ok(chord)
+    }
+
+    /// Builds the chord of a key press, undoing the symbols Shift produces on digits and letters.
+    pub fn from_event(event: &EventKey) -> Chord {
+        let state = event.state();
+        let name = event.keyval().name().map(|n| n.to_string()).unwrap_or_default();
+
+        let mut chord = Chord {
+            ctrl: state.contains(ModifierType::CONTROL_MASK),
+            shift: state.contains(ModifierType::SHIFT_MASK),
+            alt: state.contains(ModifierType::MOD1_MASK),
+            key: name.to_lowercase(),
+        };
+
+        // The symbol on Shift + digit depends on the layout, e.g. "!" or "\"", so the digit is read
+        // from the key itself. Layouts that need Shift to type digits already give the digit.
+        if chord.shift && !is_digit(&chord.key) {
+            if let Some(digit) = unshifted_key(event).filter(|key| is_digit(key)) {
+                chord.key = digit;
+            }
+        }
+        if PUNCTUATION.iter().any(|(symbol, _)| *symbol == chord.key) {
+            chord.shift = false;
+        }
+
+        chord
+    }
+
+    /// Whether the chord types a character, i.e. plain punctuation or Shift + digit,
+    /// which text fields should receive as text.
+    pub fn is_typed_text(&self) -> bool {
+        !self.ctrl
+            && !self.alt
+            && (PUNCTUATION.iter().any(|(name, _)| *name == self.key) || (self.shift && is_digit(&self.key)))
+    }
+
+    /// Formats the chord for hints, matching the nav bar style, e.g. "Shift + 1" or "ESC".
+    pub fn display(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        if self.ctrl {
+            parts.push("Ctrl".to_string());
+        }
+        if self.alt {
+            parts.push("Alt".to_string());
+        }
+        if self.shift {
+            parts.push("Shift".to_string());
+        }
+
+        let key = match PUNCTUATION.iter().find(|(name, _)| *name == self.key) {
+            Some((_, symbol)) => symbol.to_string(),
+            None if self.key == "escape" => "ESC".to_string(),
+            None if self.key.len() == 1 => self.key.to_uppercase(),
+            None => {
+                let mut chars = self.key.chars();
+                chars.next().map(|c| c.to_uppercase().collect::<String>() + chars.as_str()).unwrap_or_default()
+            }
+        };
+        parts.push(key);
+
+        parts.join(" + ")
+    }
+}
+
+fn is_digit(key: &str) -> bool {
+    key.len() == 1 && key.chars().all(|c| c.is_ascii_digit())
+}
+
+/// The lowercase name of the key pressed as it reads without any modifiers, from the keyboard layout.
+fn unshifted_key(event: &EventKey) -> Option<String> {
+    let keymap = gtk::gdk::Keymap::for_display(&gtk::gdk::Display::default()?)?;
+    let (keyval, ..) = keymap.translate_keyboard_state(
+        u32::from(event.hardware_keycode()),
+        ModifierType::empty(),
+        i32::from(event.group()),
+    )?;
+    gtk::gdk::keys::Key::from(keyval).name().map(|name| name.to_lowercase())
+}
+
+/// An action bound to a key on some screens.
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub action: Action,
+    pub chord: Chord,
+    /// Screens the binding is active on. Empty means every screen.
+    pub screens: Vec<Screens>,
+}
+
+impl Binding {
+    pub fn is_active_on(&self, screen: Screens) -> bool {
+        self.screens.is_empty() || self.screens.contains(&screen)
+    }
+}
+
+/// The registry of key bindings. Key handling, the nav bar hints and the help overlay all read from it.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+/// Shorthand for the default bindings table.
+fn bind(action: Action, key: &str, screens: &[Screens]) -> Binding {
+    Binding {
+        action,
+        chord: Chord::parse(key).expect("default key bindings must parse"),
+        screens: screens.to_vec(),
+    }
+}
+
+impl Keymap {
+    /// The built-in bindings, in the order they are listed in hints.
+    pub fn defaults() -> Keymap {
+        use Screens::*;
+
+        let bindings = vec![
+            bind(Action::Quit, "Escape", &[]),
+            bind(Action::NewItinerary, "Shift+1", &[Home]),
+            bind(Action::Back, "Shift+1", &[Create, Debug, Destinations, Flights, Places, Open, Import, Export, Drafts]),
+            bind(Action::OpenItineraries, "Shift+2", &[Home]),
+            bind(Action::Destinations, "Shift+3", &[Create]),
+            bind(Action::Flights, "Shift+4", &[Create]),
+            bind(Action::Places, "Shift+5", &[Create]),
+            bind(Action::AddTestItinerary, "Shift+6", &[Debug]),
+            bind(Action::DiagnosticBundle, "Ctrl+B", &[Debug]),
+            bind(Action::Import, "Shift+7", &[Create]),
+            bind(Action::SaveItinerary, "Shift+9", &[Create]),
+            bind(Action::SaveDestination, "Shift+9", &[Destinations]),
+            bind(Action::SaveFlight, "Shift+9", &[Flights]),
+            bind(Action::SavePlace, "Shift+9", &[Places]),
+            bind(Action::ConfirmImport, "Shift+9", &[Import]),
+            bind(Action::ConfirmExport, "Shift+9", &[Export]),
+            bind(Action::ReloadConfig, "Ctrl+R", &[]),
+            // Letters take Ctrl, since Shift + letter is a capital typed into a field
+            bind(Action::Export, "Ctrl+E", &[Home, Create]),
+            bind(Action::Debug, "Ctrl+Shift+F", &[Home, Create]),
+            bind(Action::Drafts, "Ctrl+D", &[Home, Create]),
+            bind(Action::PickDate, "Alt+Down", &[Create, Destinations, Flights, Places]),
+            bind(Action::Help, "?", &[]),
+            bind(Action::CommandPalette, "Ctrl+P", &[]),
+            bind(Action::Undo, "Ctrl+Z", &[]),
+            bind(Action::Redo, "Ctrl+Y", &[]),
+        ];
+
+        Keymap { bindings }
+    }
+
+    /// Builds the keymap from the defaults and the `[keys]` table of the config file,
+    /// which maps action names to keys, e.g. `save_itinerary = "Ctrl+S"`.
+    /// Returns every problem found: unknown actions, unparsable keys and conflicting bindings.
+    pub fn with_overrides(overrides: &HashMap<String, String>) -> Result<Keymap, Vec<String>> {
+        let mut keymap = Keymap::defaults();
+        let mut problems = Vec::new();
+
+        for (name, key) in overrides {
+            let action = match Action::from_name(name) {
+                Some(action) => action,
+                None => {
+                    problems.push(format!("keys: unknown action '{}'", name));
+                    continue;
+                }
+            };
+
+            match Chord::parse(key) {
+                Ok(chord) => {
+                    for binding in keymap.bindings.iter_mut().filter(|b| b.action == action) {
+                        binding.chord = chord.clone();
+                    }
+                }
+                Err(e) => problems.push(format!("keys.{}: {}", name, e)),
+            }
+        }
+
+        problems.extend(keymap.conflicts());
+
+        if problems.is_empty() {
+            Ok(keymap)
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Lists bindings that share a key on a screen where both are active.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+
+        for (i, a) in self.bindings.iter().enumerate() {
+            for b in self.bindings.iter().skip(i + 1) {
+                if a.chord != b.chord || a.action == b.action {
+                    continue;
+                }
+                let overlap = a.screens.is_empty() || b.screens.is_empty() || a.screens.iter().any(|s| b.screens.contains(s));
+                if overlap {
+                    conflicts.push(format!(
+                        "keys: '{}' is bound to both '{}' and '{}'",
+                        a.chord.display(),
+                        a.action.name(),
+                        b.action.name()
+                    ));
+                }
+            }
+        }
+
+        conflicts
+    }
+
+    /// Finds the action bound to a chord on a screen.
+    pub fn action_for(&self, screen: Screens, chord: &Chord) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.chord == *chord && binding.is_active_on(screen))
+            .map(|binding| binding.action)
+    }
+
+    /// Bindings active on a screen, in registry order.
+    pub fn bindings_for(&self, screen: Screens) -> Vec<&Binding> {
+        self.bindings.iter().filter(|binding| binding.is_active_on(screen)).collect()
+    }
+
+    /// The key bound to an action on a screen, formatted for display.
+    pub fn key_for(&self, screen: Screens, action: Action) -> Option<String> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.action == action && binding.is_active_on(screen))
+            .map(|binding| binding.chord.display())
+    }
+}
+
+/// The keymap built from the current config. Falls back to the defaults if the config's keys are invalid,
+/// which only happens if they were never validated.
+pub fn current() -> Keymap {
+    Keymap::with_overrides(&globals::get_config().keys).unwrap_or_else(|_| Keymap::defaults())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(ctrl: bool, shift: bool, alt: bool, key: &str) -> Chord {
+        Chord { ctrl, shift, alt, key: key.to_string() }
+    }
+
+    fn overrides(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(name, key)| (name.to_string(), key.to_string())).collect()
+    }
+
+    #[test]
+    fn parse_reads_modifiers_and_key_names() {
+        assert_eq!(Chord::parse("Shift+1"), Ok(chord(false, true, false, "1")));
+        assert_eq!(Chord::parse(" ctrl + shift + F "), Ok(chord(true, true, false, "f")));
+        assert_eq!(Chord::parse("Alt+Down"), Ok(chord(false, false, true, "down")));
+        assert_eq!(Chord::parse("Esc"), Ok(chord(false, false, false, "escape")));
+        assert_eq!(Chord::parse("Enter"), Ok(chord(false, false, false, "return")));
+    }
+
+    #[test]
+    fn parse_maps_punctuation_and_drops_shift_for_it() {
+        assert_eq!(Chord::parse("?"), Ok(chord(false, false, false, "question")));
+        assert_eq!(Chord::parse("Shift+?"), Ok(chord(false, false, false, "question")));
+        assert_eq!(Chord::parse("Ctrl++"), Ok(chord(true, false, false, "plus")));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_modifiers_and_keys() {
+        assert!(Chord::parse("Hyper+X").is_err());
+        assert!(Chord::parse("Ctrl+€").is_err());
+        assert!(Chord::parse("").is_err());
+    }
+
+    #[test]
+    fn display_matches_the_nav_bar_style() {
+        assert_eq!(Chord::parse("Shift+1").unwrap().display(), "Shift + 1");
+        assert_eq!(Chord::parse("Escape").unwrap().display(), "ESC");
+        assert_eq!(Chord::parse("Ctrl+Alt+Down").unwrap().display(), "Ctrl + Alt + Down");
+        assert_eq!(Chord::parse("?").unwrap().display(), "?");
+    }
+
+    #[test]
+    fn plain_punctuation_and_shifted_digits_are_typed_text() {
+        assert!(Chord::parse("?").unwrap().is_typed_text());
+        assert!(Chord::parse("Shift+1").unwrap().is_typed_text());
+        assert!(!Chord::parse("Ctrl+?").unwrap().is_typed_text());
+        assert!(!Chord::parse("Ctrl+Shift+1").unwrap().is_typed_text());
+        assert!(!Chord::parse("1").unwrap().is_typed_text());
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        assert_eq!(Keymap::defaults().conflicts(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn overrides_conflicting_on_a_shared_screen_are_reported() {
+        // The command palette is bound on every screen, so any screen overlaps
+        let problems = Keymap::with_overrides(&overrides(&[("drafts", "Ctrl+P")])).err().unwrap();
+        assert_eq!(problems, vec!["keys: 'Ctrl + P' is bound to both 'drafts' and 'command_palette'".to_string()]);
+    }
+
+    #[test]
+    fn overrides_sharing_a_key_on_different_screens_are_allowed() {
+        // Import is only bound on CREATE and saving a flight only on FLIGHTS
+        let keymap = Keymap::with_overrides(&overrides(&[("save_flight", "Shift+7")])).unwrap();
+        let chord = Chord::parse("Shift+7").unwrap();
+        assert_eq!(keymap.action_for(Screens::Flights, &chord), Some(Action::SaveFlight));
+        assert_eq!(keymap.action_for(Screens::Create, &chord), Some(Action::Import));
+    }
+
+    #[test]
+    fn overrides_with_unknown_actions_or_bad_keys_are_reported() {
+        let problems = Keymap::with_overrides(&overrides(&[("fly", "Shift+8")])).err().unwrap();
+        assert_eq!(problems, vec!["keys: unknown action 'fly'".to_string()]);
+
+        let problems = Keymap::with_overrides(&overrides(&[("undo", "Hyper+Z")])).err().unwrap();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("keys.undo: unknown modifier"));
+    }
+}
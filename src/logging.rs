@@ -0,0 +1,123 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::prelude::*;
+
+use crate::paths;
+
+/// Size past which the log file is rotated.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Number of rotated files kept next to the current one, e.g. `app.log.1` to `app.log.3`.
+const ROTATED_FILES: usize = 3;
+
+/// Least serious level written to the log file. Debug records, e.g. every unhandled key press,
+/// are only kept in memory.
+const FILE_LEVEL: Level = Level::Info;
+
+/// Set once writing the log file failed, so the failure is reported only once.
+static FILE_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// How serious a log record is, most serious first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    /// Every level, most serious first.
+    pub const ALL: [Level; 4] = [Level::Error, Level::Warn, Level::Info, Level::Debug];
+
+    /// Name as written in the log, e.g. "ERROR".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        }
+    }
+}
+
+/// One entry of the log.
+#[derive(Debug, Clone)]
+pub struct Record {
+    /// Position in the log, counting from 1, so readers can ask for the records after the last one they saw.
+    pub seq: u64,
+    pub time: DateTime<Local>,
+    pub level: Level,
+    /// Module that logged it, e.g. "database".
+    pub module: String,
+    pub message: String,
+}
+
+impl Record {
+    /// Builds a record stamped with the current time, to be numbered when added to the log. `module` may be a full path from `module_path!()`.
+    pub fn new(level: Level, module: &str, message: &str) -> Record {
+        Record {
+            seq: 0,
+            time: Local::now(),
+            level,
+            module: module.rsplit("::").next().unwrap_or(module).to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Formats the record as one line, e.g. "2024-05-01 14:03:12.345 ERROR [database] Could not parse ...".
+    pub fn line(&self) -> String {
+        format!("{} {:<5} [{}] {}", self.time.format("%Y-%m-%d %H:%M:%S%.3f"), self.level.name(), self.module, self.message)
+    }
+}
+
+/// Log file in the XDG state directory, e.g. `~/.local/state/itinerary_planner/itinerary_planner.log`.
+pub fn file_path() -> PathBuf {
+    paths::state_dir().join("itinerary_planner.log")
+}
+
+/// Path of the n-th rotated file, e.g. `itinerary_planner.log.1`.
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    PathBuf::from(format!("{}.{}", path.display(), n))
+}
+
+/// Moves the log file to `.1`, shifting the older files up and dropping the oldest.
+fn rotate(path: &Path) -> io::Result<()> {
+    for n in (1..ROTATED_FILES).rev() {
+        let from = rotated_path(path, n);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, n + 1))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))
+}
+
+/// Appends a line to the log file, rotating it first once it grew too big.
+fn append(path: &Path, line: &str) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::metadata(path).map_or(false, |metadata| metadata.len() >= MAX_FILE_BYTES) {
+        rotate(path)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+/// Writes a record to the log file, unless it is less serious than `FILE_LEVEL`. A file that can't be written is reported on stderr once,
+/// since there is nowhere else left to log it; the in-memory log keeps working.
+pub fn write_to_file(record: &Record) {
+    if record.level > FILE_LEVEL || FILE_FAILED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let path = file_path();
+    if let Err(e) = append(&path, &record.line()) {
+        FILE_FAILED.store(true, Ordering::Relaxed);
+        eprintln!("Log file {} could not be written, logging to memory only: {}", path.display(), e);
+    }
+}
@@ -5,6 +5,9 @@ use gtk::gdk::EventKey;
 use gtk::prelude::*;
 use gtk::{Application, ApplicationWindow};
 
+use crate::logging::Level;
+use crate::routes::{Navigator, Route};
+
 // Project-wide modules
 mod globals;
 mod database;
@@ -18,145 +21,151 @@ mod home;
 mod places;
 mod routes;
 mod open;
-
-/// Logs messages to the application's debug log.
-fn log(message: &str) {
-    globals::add_to_debug_log(message);
+mod ics;
+mod import;
+mod printable;
+mod pdf;
+mod bundle;
+mod csv_io;
+mod cli;
+mod paths;
+mod options;
+mod config;
+mod actions;
+mod keymap;
+mod export;
+mod help;
+mod palette;
+mod history;
+mod logging;
+mod diagnostics;
+mod validation;
+mod forms;
+mod datepicker;
+mod recovery;
+mod drafts;
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
 }
 
 /// Sets up key press event handling for the window.
-fn listen(window: &ApplicationWindow, app: &Application) {
-    let current_screen = Rc::new(RefCell::new(String::from("HOME")));
-
+fn listen(window: &ApplicationWindow, app: &Application, navigator: Rc<RefCell<Navigator>>) {
     window.connect_key_press_event({
         let app = app.clone();
         let window = window.clone();
         move |_, event| {
-            handle_key_press(event, &app, &window, &current_screen);
+            handle_key_press(event, &app, &window, &navigator);
             Inhibit(false)
         }
     });
 }
 
-/// Processes key press events and updates the screen state accordingly.
+/// Processes key press events by looking the key up in the keymap registry for the current screen.
 fn handle_key_press(
     event: &EventKey,
     app: &Application,
     window: &ApplicationWindow,
-    current_screen: &Rc<RefCell<String>>,
+    navigator: &Rc<RefCell<Navigator>>,
 ) {
-    let key = event.keyval();
-
-    match key {
-        // Escape
-        gtk::gdk::keys::constants::Escape => app.quit(), // Quit application
-        // Shift + 1
-        gtk::gdk::keys::constants::exclam => {
-            // Toggle between HOME and CREATE screen
-            if *current_screen.borrow() != "HOME" {
-                routes::route_back(window, current_screen);
-            } else {
-                routes::route_forward(window, current_screen, "CREATE");
-            }
-        }
-        // Shift + 2
-        gtk::gdk::keys::constants::at => {
-            // Only switch to OPEN if Shift + 2 is pressed in HOME screen.
-            if *current_screen.borrow() == "HOME" {
-                routes::route_forward(window, current_screen, "OPEN");
-            }
-        }
-        // Shift + 3
-        gtk::gdk::keys::constants::numbersign => {
-            routes::route_forward(window, current_screen, "DESTINATIONS");
-        }
-        // Shift + 4
-        gtk::gdk::keys::constants::dollar => {
-            routes::route_forward(window, current_screen, "FLIGHTS");
-        }
-        // Shift + 5
-        gtk::gdk::keys::constants::percent => {
-            routes::route_forward(window, current_screen, "PLACES");
-        }
-        // Shift + 6
-        gtk::gdk::keys::constants::asciicircum => {
-            // Test function: Adds a sample itinerary entry
-            let database = globals::get_database();
-            let database_instance = database.lock().unwrap();
-
-            log("Adding test itinerary with title: Yami");
-            match database_instance.add_itinerary("Yami".to_string()) {
-                Err(e) => log(&format!(
-                    "Error adding itinerary in database: {}",
-                    e
-                )),
-                Ok(s) => log(&format!("Itinerary added: {}", s)),
-            }
-        }
-        // Shift + 9
-        gtk::gdk::keys::constants::parenleft => {
-            // In CREATE screen.
-            let database = globals::get_database();
-            let mut database_instance = database.lock().unwrap();
-
-            if *current_screen.borrow() == "CREATE" {
-                match database_instance.store_session_storage_in_database() {
-                    Ok(s) => {
-                        if s == "SUCCESS" {
-                            log("Success in storing session storage to database.");
-                            routes::route_back(window, current_screen);
-                        } 
-                        else if s == "ERROR_NO_TITLE" {
-                            log("Error in database.rs in store_session_storage_in_database(): Session storage key 'title' had no value..");
-                            routes::route_back(window, current_screen);
-                            routes::route_forward(window, current_screen, "DEBUG");
-                        }
-                    }
-                    Err(e) => {
-                        log(&format!("Error in main.rs: {}", e));
-                        routes::route_back(window, current_screen);
-                        routes::route_forward(window, current_screen, "DEBUG");
-                    }
-                }
-            }
+    let chord = keymap::Chord::from_event(event);
+    // Keys in a popover, e.g. the date picker, are handled by the popover
+    if window.focus().map_or(false, |widget| widget.ancestor(gtk::Popover::static_type()).is_some()) {
+        return;
+    }
+    // "?", "!" and the like typed into a field are text, not shortcuts
+    if chord.is_typed_text() && window.focus().map_or(false, |widget| widget.is::<gtk::Entry>()) {
+        return;
+    }
+    let screen = navigator.borrow().screen();
 
-            // In DESTINATIONS screen.
-            if *current_screen.borrow() == "DESTINATIONS" {
-                database_instance.add_current_destination_to_session_storage();
-                routes::route_back(window, current_screen);
-            }
-        }
-        // Shift + F
-        gtk::gdk::keys::constants::F => {
-            routes::route_forward(window, current_screen, "DEBUG");
-        }
-        _ => println!("Unhandled key press: {:?}", key),
+    match keymap::current().action_for(screen, &chord) {
+        Some(action) => actions::perform(action, app, window, navigator),
+        None => log(Level::Debug, &format!("Unhandled key press: {}", chord.display())),
     }
 }
 
 fn main() {
+    // App options are shared by the GTK app and the headless subcommands
+    let args: Vec<String> = std::env::args().collect();
+    let (options, args) = match options::parse(&args) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    // Invalid config is reported and the defaults are used instead
+    match config::load() {
+        Ok(config) => globals::set_config(config),
+        Err(message) => {
+            log(Level::Warn, &format!("Invalid config, using defaults: {}", message));
+        }
+    }
+    globals::set_database_path(&options.database_path(globals::get_config().database.as_deref()));
+
+    // Headless subcommands work on the same database without starting GTK
+    if cli::is_command(&args) {
+        std::process::exit(cli::run(&args));
+    }
+
     let app = Application::new(Some("com.example.ItineraryPlanner"), Default::default());
 
-    app.connect_activate(|app| {
+    app.connect_activate(move |app| {
         // Initialize database session and log session ID
-        let database = globals::get_database();
-        let db_instance = database.lock().unwrap();
-        log(&format!("Session ID: {}", db_instance.session_id()));
+        {
+            let database = globals::get_database();
+            let db_instance = database.lock().unwrap();
+            log(Level::Info, &format!("Session ID: {}", db_instance.session_id()));
+        }
 
         // Set up main application window
+        let config = globals::get_config();
         let window = ApplicationWindow::new(app);
-        window.set_title("Black Window");
-        window.set_default_size(1300, 800);
-        window.style_context().add_class("black-window");
+        window.set_title(&config.window_title);
+        window.set_default_size(config.window_width, config.window_height);
+        window.style_context().add_class(config.theme_class());
 
-        // Load and display the home screen
+        // Bind the buttons in the views to the actions, then load and display the home screen
+        let navigator = Rc::new(RefCell::new(Navigator::new()));
+        actions::install(app, &window, &navigator);
         home::view(&window);
-        
+
+        // Open the itinerary requested on the command line in the CREATE screen
+        if let Some(title) = &options.open {
+            let exists = {
+                let database = globals::get_database();
+                let db_instance = database.lock().unwrap();
+                db_instance.get_itinerary(title).is_some()
+            };
+            if exists {
+                routes::route_forward(&window, &navigator, Route::Create { itinerary: Some(title.clone()) });
+            } else {
+                log(Level::Error, &format!("No itinerary named '{}' to open.", title));
+            }
+        }
+
+        // Closing the window asks about unsaved changes like quitting with the keyboard does
+        window.connect_delete_event({
+            let app = app.clone();
+            let navigator = navigator.clone();
+            move |window, _| {
+                actions::perform(actions::Action::Quit, &app, window, &navigator);
+                Inhibit(true)
+            }
+        });
+
+        // Offer to restore the drafts the last run did not save, then keep autosaving them
+        recovery::offer_recovery(&window, &navigator);
+        recovery::start_autosave();
+
         // Enable key press event listening
-        listen(&window, app);
+        listen(&window, app, navigator);
 
         // Apply custom styling from CSS file
-        let css_provider = util::load_css("style.css");
+        let css_provider = util::load_css(&options.stylesheet);
         gtk::StyleContext::add_provider_for_screen(
             &gtk::gdk::Screen::default().expect("Failed to initialize GTK screen"),
             &css_provider,
@@ -164,5 +173,6 @@ fn main() {
         );
     });
 
-    app.run();
-}
\ No newline at end of file
+    // GTK only sees the program name, the app options were handled above
+    app.run_with_args(&args[..1]);
+}
@@ -1,30 +1,37 @@
+use gtk::glib::ToVariant;
 use gtk::prelude::*;
-use gtk::Fixed;
+use gtk::Orientation;
 use crate::util;
 use crate::universals;
 use crate::globals;
 
+/// Name of the window action that opens a saved itinerary, taking its title.
+pub const OPEN_ACTION: &str = "open_itinerary";
+
+/// Builds and displays the "Open" screen, listing the saved itineraries.
+/// Picking one opens it on the CREATE screen.
 pub fn view(window: &gtk::ApplicationWindow) {
-    let fixed = Fixed::new();
-    let database = globals::get_database();
-    let database_instance = database.lock().unwrap();
+    let content = gtk::Box::new(Orientation::Vertical, 5);
+    let titles = {
+        let database = globals::get_database();
+        let database_instance = database.lock().unwrap();
+        database_instance.list_all_itineraries()
+    };
 
-    util::add_text("ITINERARY PLANNER", &[40, 100, 50, 20], &[0.1, 0.1], &fixed, Some("big"));
-    util::add_text("Saved Itineraries:",                      &[140, 20, 50, 20], &[0.5, 0.5], &fixed, Some("medium"));
+    util::add_text("ITINERARY PLANNER", &[0, 50, 0, 0], &[0.0, 0.5], &content, Some("big"));
+    util::add_text("Saved Itineraries:",                      &[0, 20, 0, 0], &[0.0, 0.5], &content, Some("medium"));
 
-    let mut i = 0;
-    for itinerary in database_instance.list_all_itineraries() {
-        util::add_text(&format!("{}. {}", i + 2, itinerary), &[(180 + (i * 25)), 20, 100, 20], &[0.1, 0.1], &fixed, None);
-        i += 1;
+    for (i, itinerary) in titles.iter().enumerate() {
+        // Printed as a GVariant string, so quotes in the title are escaped
+        let action = format!("win.{}({})", OPEN_ACTION, itinerary.to_variant().print(false));
+        let label = format!("{}. {}", i + 1, itinerary);
+        let button = util::add_text_button_manual(&label, &[5, 5, 0, 0], &[0, 0], Some("option"), Some(&action));
+        content.pack_start(&button, false, false, 0);
     }
 
-    if database_instance.list_all_itineraries().len() == 0 {
-        util::add_text("No itineraries found.", &[180, 20, 100, 20], &[0.1, 0.1], &fixed, None);
+    if titles.is_empty() {
+        util::add_text("No itineraries found.", &[0, 0, 0, 0], &[0.0, 0.5], &content, None);
     }
 
-    let nav = universals::nav(&universals::Screens::Open);
-    fixed.add(&nav);
-
-    window.add(&fixed);
-    window.show_all();
-}
\ No newline at end of file
+    universals::page(window, &content, &universals::Screens::Open);
+}
@@ -0,0 +1,63 @@
+use crate::paths;
+
+/// Options accepted on the command line, both by the GTK app and by the headless subcommands.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Database file given with --database. Falls back to the config file, then the XDG default.
+    pub database: Option<String>,
+    pub stylesheet: String,
+    /// Itinerary to load into the CREATE screen at startup.
+    pub open: Option<String>,
+}
+
+pub const OPTIONS_USAGE: &str = "  --database <PATH>                             Database file (default: $XDG_DATA_HOME/itinerary_planner/database.json)
+  --stylesheet <PATH>                           Stylesheet (default: $XDG_CONFIG_HOME/itinerary_planner/style.css)
+  --open <ITINERARY>                            Open an itinerary at startup";
+
+/// Splits the app options out of the command line. Returns the options and the remaining
+/// arguments (program name first), or a message if an option is missing its value.
+pub fn parse(args: &[String]) -> Result<(Options, Vec<String>), String> {
+    let mut options = Options {
+        database: None,
+        stylesheet: paths::default_stylesheet().to_string_lossy().to_string(),
+        open: None,
+    };
+    let mut remaining = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        // Both "--name value" and "--name=value" are accepted
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+
+        if !["--database", "--stylesheet", "--open"].contains(&name.as_str()) {
+            remaining.push(arg.clone());
+            continue;
+        }
+
+        let value = match inline_value.or_else(|| iter.next().cloned()) {
+            Some(value) => value,
+            None => return Err(format!("Missing value for {}", name)),
+        };
+
+        match name.as_str() {
+            "--database" => options.database = Some(value),
+            "--stylesheet" => options.stylesheet = value,
+            _ => options.open = Some(value),
+        }
+    }
+
+    Ok((options, remaining))
+}
+
+impl Options {
+    /// Resolves the database path: command line first, then the config file, then the XDG default.
+    pub fn database_path(&self, config_database: Option<&str>) -> String {
+        self.database
+            .clone()
+            .or_else(|| config_database.map(|path| path.to_string()))
+            .unwrap_or_else(|| paths::default_database().to_string_lossy().to_string())
+    }
+}
@@ -0,0 +1,276 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::{Application, ApplicationWindow, ListBox, Orientation, SelectionMode, WindowPosition, WindowType};
+
+use crate::actions::{self, Action};
+use crate::keymap;
+use crate::routes::{self, Navigator, Route};
+use crate::universals::Screens;
+use crate::{globals, util};
+
+/// Number of matches listed at once.
+const MAX_RESULTS: usize = 10;
+
+/// Something the palette can run.
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    /// An action bound on the current screen, with its key for display.
+    Action(Action, String),
+    /// Opens a saved itinerary on the CREATE screen.
+    Open(String),
+    /// Opens the EXPORT screen with a saved itinerary selected.
+    Export(String),
+    /// Switches to a draft by ID, with its title for display.
+    Draft(u64, String),
+}
+
+impl Item {
+    fn label(&self) -> String {
+        match self {
+            Item::Action(action, key) => format!("{}    {}", util::capitalize(action.description()), key),
+            Item::Open(title) => format!("Open itinerary: {}", title),
+            Item::Export(title) => format!("Export itinerary: {}", title),
+            Item::Draft(_, title) => format!("Switch to draft: {}", title),
+        }
+    }
+
+    /// Text matched against the query. Action names are included so "export ics" finds the export actions.
+    fn search_text(&self) -> String {
+        match self {
+            Item::Action(action, _) => format!("{} {}", action.description(), action.name().replace('_', " ")),
+            Item::Open(title) => format!("open {}", title),
+            Item::Export(title) => format!("export {} ics csv pdf html markdown bundle", title),
+            Item::Draft(_, title) => format!("draft {}", title),
+        }
+    }
+}
+
+/// Scores how well `text` matches `query` as a fuzzy subsequence, ignoring case and spaces.
+/// Consecutive letters and letters at the start of words score higher; `None` means no match.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let mut score = 0;
+    let mut matched = 0;
+    let mut last: Option<usize> = None;
+
+    for (i, c) in text.iter().enumerate() {
+        if matched == query.len() {
+            break;
+        }
+        if *c != query[matched] {
+            continue;
+        }
+
+        score += 1;
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        match last {
+            Some(previous) if previous + 1 == i => score += 5,
+            Some(previous) => score -= ((i - previous - 1) as i32).min(3),
+            None => {}
+        }
+
+        last = Some(i);
+        matched += 1;
+    }
+
+    if matched == query.len() {
+        Some(score)
+    } else {
+        None
+    }
+}
+
+/// Everything the palette can offer on a screen: the actions bound there, the other drafts,
+/// then the saved itineraries.
+fn items(screen: Screens) -> Vec<Item> {
+    let keymap = keymap::current();
+    let mut items: Vec<Item> = keymap
+        .bindings_for(screen)
+        .iter()
+        .filter(|binding| binding.action != Action::CommandPalette)
+        .map(|binding| Item::Action(binding.action, binding.chord.display()))
+        .collect();
+
+    let (drafts, titles) = {
+        let database = globals::get_database();
+        let db_instance = database.lock().unwrap();
+        (db_instance.list_drafts(), db_instance.list_all_itineraries())
+    };
+    for draft in drafts.into_iter().filter(|draft| !draft.active) {
+        items.push(Item::Draft(draft.id, draft.title));
+    }
+    for title in titles {
+        items.push(Item::Open(title.clone()));
+        items.push(Item::Export(title));
+    }
+
+    items
+}
+
+/// Items matching the query, best first. Ties keep the order of `items`.
+fn search(items: &[Item], query: &str) -> Vec<Item> {
+    let mut scored: Vec<(i32, usize)> = items
+        .iter()
+        .enumerate()
+        .filter_map(|(index, item)| fuzzy_score(query, &item.search_text()).map(|score| (score, index)))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    scored.into_iter().take(MAX_RESULTS).map(|(_, index)| items[index].clone()).collect()
+}
+
+/// Runs the picked item. Itineraries are opened from wherever the palette was used.
+fn run(item: &Item, app: &Application, window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    match item {
+        Item::Action(action, _) => actions::perform(*action, app, window, navigator),
+        Item::Open(title) => actions::open_itinerary(window, navigator, title.clone()),
+        Item::Export(title) => routes::route_jump(window, navigator, Route::Export { itinerary: Some(title.clone()) }),
+        Item::Draft(id, _) => actions::switch_draft(window, navigator, *id),
+    }
+}
+
+/// Refills the result list for the query and selects the best match.
+fn fill(list: &ListBox, results: &Rc<RefCell<Vec<Item>>>, items: &[Item], query: &str) {
+    for child in list.children() {
+        list.remove(&child);
+    }
+
+    let matches = search(items, query);
+    for item in &matches {
+        let label = util::add_text_manual(&item.label(), &[4, 4, 10, 10], None);
+        label.set_xalign(0.0);
+        list.add(&label);
+    }
+    if matches.is_empty() {
+        let label = util::add_text_manual("No matches", &[4, 4, 10, 10], Some("navbar-item"));
+        label.set_xalign(0.0);
+        list.add(&label);
+    }
+    list.show_all();
+
+    if let Some(row) = list.row_at_index(0) {
+        list.select_row(Some(&row));
+    }
+    *results.borrow_mut() = matches;
+}
+
+/// Opens the command palette: type to fuzzy-search, Up/Down to choose, Enter to run, ESC to close.
+pub fn show(app: &Application, window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    let items = Rc::new(items(navigator.borrow().screen()));
+    let results: Rc<RefCell<Vec<Item>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let palette = gtk::Window::new(WindowType::Toplevel);
+    palette.set_transient_for(Some(window));
+    palette.set_modal(true);
+    palette.set_decorated(false);
+    palette.set_position(WindowPosition::CenterOnParent);
+    palette.set_default_size(600, -1);
+    palette.style_context().add_class("command-palette");
+
+    let container = gtk::Box::new(Orientation::Vertical, 5);
+    container.set_margin_top(15);
+    container.set_margin_bottom(15);
+    container.set_margin_start(15);
+    container.set_margin_end(15);
+
+    let entry = gtk::Entry::new();
+    entry.set_placeholder_text(Some("Search actions and itineraries"));
+    entry.style_context().add_class("text_entry");
+
+    let list = ListBox::new();
+    list.set_selection_mode(SelectionMode::Single);
+
+    container.pack_start(&entry, false, false, 0);
+    container.pack_start(&list, true, true, 0);
+    palette.add(&container);
+
+    fill(&list, &results, &items, "");
+
+    entry.connect_changed({
+        let list = list.clone();
+        let results = results.clone();
+        let items = items.clone();
+        move |entry| fill(&list, &results, &items, &entry.text())
+    });
+
+    // Runs the item in the given row after the palette is closed, so its screen gets the focus
+    let pick = {
+        let palette = palette.clone();
+        let results = results.clone();
+        let app = app.clone();
+        let window = window.clone();
+        let navigator = navigator.clone();
+        Rc::new(move |index: i32| {
+            let item = results.borrow().get(index as usize).cloned();
+            palette.close();
+            if let Some(item) = item {
+                run(&item, &app, &window, &navigator);
+            }
+        })
+    };
+
+    list.connect_row_activated({
+        let pick = pick.clone();
+        move |_, row| pick(row.index())
+    });
+
+    palette.connect_key_press_event({
+        let list = list.clone();
+        move |palette, event| {
+            let chord = keymap::Chord::from_event(event);
+            let selected = list.selected_row().map_or(0, |row| row.index());
+            let count = results.borrow().len() as i32;
+
+            match chord.key.as_str() {
+                "escape" => palette.close(),
+                "return" | "kp_enter" => pick(selected),
+                "down" if selected + 1 < count => list.select_row(list.row_at_index(selected + 1).as_ref()),
+                "up" if selected > 0 => list.select_row(list.row_at_index(selected - 1).as_ref()),
+                _ => return Inhibit(false),
+            }
+            Inhibit(true)
+        }
+    });
+
+    palette.show_all();
+    entry.grab_focus();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_needs_every_letter_in_order() {
+        assert!(fuzzy_score("jpn", "Japan").is_some());
+        assert_eq!(fuzzy_score("npj", "Japan"), None);
+        assert_eq!(fuzzy_score("japanese", "Japan"), None);
+    }
+
+    #[test]
+    fn fuzzy_score_ignores_case_and_spaces_in_the_query() {
+        assert_eq!(fuzzy_score("JA PAN", "japan"), fuzzy_score("japan", "JAPAN"));
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_score_ranks_whole_words_above_scattered_letters() {
+        let query = "jap";
+        assert_eq!(fuzzy_score(query, "Japan 2025"), fuzzy_score(query, "Trip to Japan"));
+
+        let mut texts = vec!["jumpy cap", "ninja party", "Japan 2025"];
+        texts.sort_by_key(|text| std::cmp::Reverse(fuzzy_score(query, text)));
+        assert_eq!(texts, vec!["Japan 2025", "ninja party", "jumpy cap"]);
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_consecutive_letters() {
+        assert!(fuzzy_score("exp", "export itinerary") > fuzzy_score("exp", "e x p"));
+    }
+}
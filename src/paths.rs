@@ -0,0 +1,79 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::globals;
+use crate::logging::Level;
+
+/// Name of the folder the app keeps its files in, inside each XDG base directory.
+const APP_DIR: &str = "itinerary_planner";
+
+/// Where the database was kept before the XDG directories were used, relative to the working directory.
+const LEGACY_DATABASE: &str = "database.json";
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// Resolves an XDG base directory from its environment variable, falling back to `$HOME/<fallback>`.
+/// Relative values are ignored, as the XDG spec asks.
+fn xdg_dir(variable: &str, fallback: &str) -> PathBuf {
+    let base = env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    base.join(APP_DIR)
+}
+
+/// Directory for user data such as the database, e.g. `~/.local/share/itinerary_planner`.
+pub fn data_dir() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory for user configuration such as the stylesheet, e.g. `~/.config/itinerary_planner`.
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+/// Directory for state kept between runs that isn't worth backing up, such as logs,
+/// e.g. `~/.local/state/itinerary_planner`.
+pub fn state_dir() -> PathBuf {
+    xdg_dir("XDG_STATE_HOME", ".local/state")
+}
+
+/// Default database location. A database left in the working directory by older versions
+/// is used in place until there is one in the data directory, so existing itineraries aren't lost.
+pub fn default_database() -> PathBuf {
+    let database = data_dir().join("database.json");
+    let legacy = Path::new(LEGACY_DATABASE);
+    if !database.exists() && legacy.is_file() {
+        log(
+            Level::Warn,
+            &format!(
+                "Using the database in the working directory, {}. Move it to {} to use it from anywhere.",
+                legacy.display(),
+                database.display()
+            ),
+        );
+        return legacy.to_path_buf();
+    }
+
+    database
+}
+
+/// Default stylesheet location. Uses the one in the config directory if the user has one,
+/// otherwise the stylesheet shipped next to the executable.
+pub fn default_stylesheet() -> PathBuf {
+    let user_stylesheet = config_dir().join("style.css");
+    if user_stylesheet.exists() {
+        return user_stylesheet;
+    }
+
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("style.css")))
+        .filter(|path| path.exists())
+        .unwrap_or(user_stylesheet)
+}
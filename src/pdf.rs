@@ -0,0 +1,185 @@
+use std::io;
+use std::path::Path;
+
+use gtk::cairo::{Context, FontSlant, FontWeight, PdfSurface};
+use serde_json::Value;
+
+use crate::database::BookingKind;
+use crate::printable::{self, Plan};
+
+/// A4 page size in points.
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 40.0;
+const BODY_SIZE: f64 = 10.0;
+const LINE_SPACING: f64 = 1.4;
+
+/// Converts a cairo error into the io::Error used by the rest of the export code.
+fn to_io(error: gtk::cairo::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("cairo: {}", error))
+}
+
+/// Writes text top to bottom, wrapping long lines and starting new pages as needed.
+struct PdfWriter {
+    context: Context,
+    y: f64,
+}
+
+impl PdfWriter {
+    fn new(surface: &PdfSurface) -> io::Result<PdfWriter> {
+        let context = Context::new(surface).map_err(to_io)?;
+        Ok(PdfWriter { context, y: MARGIN })
+    }
+
+    /// Finishes the current page and starts at the top of the next one.
+    fn new_page(&mut self) -> io::Result<()> {
+        self.context.show_page().map_err(to_io)?;
+        self.y = MARGIN;
+        Ok(())
+    }
+
+    /// Splits text into lines that fit the page width less the indent at the current font size.
+    /// Words wider than a line, e.g. URLs, are broken between characters.
+    fn wrap(&self, text: &str, indent: f64) -> io::Result<Vec<String>> {
+        let max_width = PAGE_WIDTH - 2.0 * MARGIN - indent;
+        let fits = |text: &str| -> io::Result<bool> { Ok(self.context.text_extents(text).map_err(to_io)?.width <= max_width) };
+        let mut lines = Vec::new();
+        let mut current = String::new();
+
+        for word in text.split_whitespace() {
+            let candidate = if current.is_empty() { word.to_string() } else { format!("{} {}", current, word) };
+            if fits(&candidate)? {
+                current = candidate;
+                continue;
+            }
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            if fits(word)? {
+                current = word.to_string();
+                continue;
+            }
+            for c in word.chars() {
+                current.push(c);
+                if !fits(&current)? && current.chars().count() > 1 {
+                    current.pop();
+                    lines.push(std::mem::replace(&mut current, c.to_string()));
+                }
+            }
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+
+        Ok(lines)
+    }
+
+    /// Writes a paragraph in the given font size and weight.
+    fn text(&mut self, text: &str, size: f64, bold: bool, indent: f64) -> io::Result<()> {
+        let weight = if bold { FontWeight::Bold } else { FontWeight::Normal };
+        self.context.select_font_face("Sans", FontSlant::Normal, weight);
+        self.context.set_font_size(size);
+
+        for line in self.wrap(text, indent)? {
+            if self.y + size * LINE_SPACING > PAGE_HEIGHT - MARGIN {
+                self.new_page()?;
+                self.context.select_font_face("Sans", FontSlant::Normal, weight);
+                self.context.set_font_size(size);
+            }
+            self.y += size * LINE_SPACING;
+            self.context.move_to(MARGIN + indent, self.y);
+            self.context.show_text(&line).map_err(to_io)?;
+        }
+
+        Ok(())
+    }
+
+    fn heading(&mut self, text: &str, size: f64) -> io::Result<()> {
+        self.y += size * 0.5;
+        self.text(text, size, true, 0.0)
+    }
+
+    fn line(&mut self, text: &str) -> io::Result<()> {
+        self.text(text, BODY_SIZE, false, 10.0)
+    }
+
+    fn gap(&mut self) {
+        self.y += BODY_SIZE * 0.6;
+    }
+}
+
+/// Renders a plan to a PDF file: one page per day, then a booking summary and emergency information.
+/// `itinerary` is the raw saved itinerary, read for its emergency fields.
+pub fn write(plan: &Plan, itinerary: &Value, path: &Path, redact_codes: bool) -> io::Result<()> {
+    let surface = PdfSurface::new(PAGE_WIDTH, PAGE_HEIGHT, path).map_err(to_io)?;
+    let mut writer = PdfWriter::new(&surface)?;
+
+    // Title page header, followed directly by the first day
+    writer.heading(&plan.title, 20.0)?;
+    writer.line(&format!("Dates: {} to {}", plan.start_date, plan.end_date))?;
+
+    for (index, day) in plan.days.iter().enumerate() {
+        if index > 0 {
+            writer.new_page()?;
+        }
+        writer.heading(&printable::day_heading(day), 14.0)?;
+
+        let sections = printable::day_sections(day);
+        if sections.is_empty() {
+            writer.line("Nothing planned.")?;
+        }
+        for (heading, entries) in sections {
+            writer.heading(heading, 11.0)?;
+            for entry in entries {
+                writer.line(&format!("- {}", printable::describe(entry, redact_codes)))?;
+            }
+        }
+    }
+
+    if !plan.unscheduled.is_empty() {
+        writer.new_page()?;
+        writer.heading("Unscheduled", 14.0)?;
+        for (section, entry) in &plan.unscheduled {
+            writer.line(&format!("- {}: {}", section, printable::describe(entry, redact_codes)))?;
+        }
+    }
+
+    // Summary of every booking with its confirmation number
+    writer.new_page()?;
+    writer.heading("Bookings", 14.0)?;
+    if plan.bookings.is_empty() {
+        writer.line("No bookings.")?;
+    }
+    for (kind, booking) in &plan.bookings {
+        let code = printable::confirmation(booking, redact_codes);
+        let code = if code.is_empty() { "-".to_string() } else { code };
+        writer.line(&format!("{}: {} | {} | Confirmation: {}", kind.label(), booking.title, booking.start, code))?;
+    }
+
+    // Emergency information
+    writer.gap();
+    writer.heading("Emergency Information", 14.0)?;
+    let field = |key: &str| itinerary.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let contact = field("emergency_contact");
+    let insurance = field("insurance");
+    writer.line(&format!("Emergency contact: {}", if contact.is_empty() { "not set" } else { &contact }))?;
+    writer.line(&format!("Travel insurance: {}", if insurance.is_empty() { "not set" } else { &insurance }))?;
+
+    writer.heading("Where we are staying", 11.0)?;
+    let mut stays = plan
+        .bookings
+        .iter()
+        .filter(|(kind, _)| *kind == BookingKind::Accommodation)
+        .peekable();
+    if stays.peek().is_none() {
+        writer.line("No accommodations booked.")?;
+    }
+    for (_, stay) in stays {
+        writer.line(&format!("- {} ({} - {}) {}", stay.title, stay.start, stay.end, stay.location))?;
+    }
+
+    writer.context.show_page().map_err(to_io)?;
+    // Writing the file happens on finish, so a full disk or missing permissions only show up here
+    surface.finish();
+    surface.status().map_err(to_io)
+}
@@ -1,19 +1,21 @@
-use gtk::prelude::*;
-use gtk::Fixed;
-use crate::util;
-use crate::universals;
+use crate::forms::{self, FieldSpec, FormSpec, Kind};
+use crate::universals::Screens;
 
-pub fn view(window: &gtk::ApplicationWindow) {
-    let fixed = Fixed::new();
-
-    util::add_text("ITINERARY PLANNER", &[40, 100, 50, 20], &[0.1, 0.1], &fixed, Some("big"));
-
-    util::add_text("Options",                      &[140, 20, 50, 20], &[0.5, 0.5], &fixed, None);
-    util::add_text("Add Place: ",                &[180, 20, 100, 20], &[0.1, 0.1], &fixed, None);
+/// A place to visit, added to the saved itinerary being edited by the save place action.
+const FORM: FormSpec = FormSpec {
+    screen: Screens::Places,
+    heading: "Add Place: ",
+    fields: &[
+        FieldSpec::new("Name: ", "place_name", Kind::Text).required(),
+        FieldSpec::new("Address: ", "place_address", Kind::Text),
+        FieldSpec::new("Date: ", "place_date", Kind::Date),
+        FieldSpec::new("Time: ", "place_time", Kind::Time),
+        FieldSpec::new("Cost: ", "place_cost", Kind::Amount),
+        FieldSpec::new("Notes: ", "place_notes", Kind::Text),
+    ],
+    options: &[],
+};
 
-    let nav = universals::nav(&universals::Screens::Places);
-    fixed.add(&nav);
-
-    window.add(&fixed);
-    window.show_all();
-}
\ No newline at end of file
+pub fn view(window: &gtk::ApplicationWindow) {
+    forms::view(window, &FORM);
+}
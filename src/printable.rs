@@ -0,0 +1,365 @@
+use chrono::{Duration, NaiveDate};
+use serde_json::Value;
+
+use crate::database::{Booking, BookingKind, Database, Destination, Place};
+use crate::util;
+
+/// Upper bound on the number of days laid out for a single itinerary.
+const MAX_DAYS: i64 = 366;
+
+/// Something listed on a day: a booking or a place to visit.
+#[derive(Debug, Clone)]
+pub enum Entry {
+    Booking(Booking),
+    Place(Place),
+}
+
+impl Entry {
+    /// The date the entry starts on, as stored.
+    fn start(&self) -> &str {
+        match self {
+            Entry::Booking(booking) => &booking.start,
+            Entry::Place(place) => &place.date,
+        }
+    }
+}
+
+/// Everything happening on one day of an itinerary.
+#[derive(Debug, Clone)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub destination: Option<String>,
+    pub flights: Vec<Entry>,
+    pub travel: Vec<Entry>,
+    pub lodging: Vec<Entry>,
+    pub places: Vec<Entry>,
+}
+
+/// A saved itinerary laid out day by day, ready to be rendered into a document.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub title: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub days: Vec<Day>,
+    /// Entries without a date that could be placed on a day, with the section they belong to.
+    pub unscheduled: Vec<(String, Entry)>,
+    /// Every flight, travel leg and accommodation, used for booking summaries.
+    pub bookings: Vec<(BookingKind, Booking)>,
+}
+
+/// Hides all but the last two characters of a confirmation code.
+pub fn redact(code: &str) -> String {
+    let count = code.chars().count();
+    if count <= 2 {
+        return "*".repeat(count);
+    }
+
+    let visible: String = code.chars().skip(count - 2).collect();
+    format!("{}{}", "*".repeat(count - 2), visible)
+}
+
+/// Returns the confirmation code of a booking as it should be printed.
+pub fn confirmation(booking: &Booking, redact_codes: bool) -> String {
+    if redact_codes {
+        redact(&booking.confirmation)
+    } else {
+        booking.confirmation.clone()
+    }
+}
+
+/// Finds the destination whose date range covers the given day.
+fn destination_on(destinations: &[Destination], date: NaiveDate) -> Option<String> {
+    destinations
+        .iter()
+        .find(|destination| {
+            match (util::parse_date(&destination.start_date), util::parse_date(&destination.end_date)) {
+                (Some(start), Some(end)) => start <= date && date <= end,
+                (Some(start), None) => start == date,
+                _ => false,
+            }
+        })
+        .map(|destination| destination.title.clone())
+}
+
+/// Adds an entry to the section of the day it starts on. Returns false if no day matches.
+fn place_on_day(section: &str, entry: &Entry, days: &mut [Day]) -> bool {
+    let day = util::parse_date(entry.start()).and_then(|date| days.iter_mut().find(|day| day.date == date));
+
+    match day {
+        Some(day) => {
+            match section {
+                "Flights" => day.flights.push(entry.clone()),
+                "Travel" => day.travel.push(entry.clone()),
+                _ => day.places.push(entry.clone()),
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// Lays a saved itinerary out day by day. Returns None if no itinerary has that title.
+pub fn build_plan(database: &Database, title: &str) -> Option<Plan> {
+    let itinerary: Value = database.get_itinerary(title)?;
+    let field = |key: &str| itinerary.get(key).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let start_date = field("start_date");
+    let end_date = field("end_date");
+
+    let destinations = database.list_destinations(title);
+    let places = database.list_places(title);
+    let bookings: Vec<(BookingKind, Booking)> = [BookingKind::Flight, BookingKind::Travel, BookingKind::Accommodation]
+        .iter()
+        .flat_map(|kind| database.list_bookings(title, *kind).into_iter().map(move |booking| (*kind, booking)))
+        .collect();
+
+    // Use the itinerary dates, falling back to the span of everything that has a date
+    let mut known_dates: Vec<NaiveDate> = bookings
+        .iter()
+        .flat_map(|(_, booking)| vec![util::parse_date(&booking.start), util::parse_date(&booking.end)])
+        .chain(places.iter().map(|place| util::parse_date(&place.date)))
+        .chain(destinations.iter().flat_map(|d| vec![util::parse_date(&d.start_date), util::parse_date(&d.end_date)]))
+        .flatten()
+        .collect();
+    known_dates.sort();
+
+    let first = util::parse_date(&start_date).or_else(|| known_dates.first().cloned());
+    let last = util::parse_date(&end_date).or_else(|| known_dates.last().cloned());
+
+    let mut days: Vec<Day> = Vec::new();
+    if let (Some(first), Some(last)) = (first, last) {
+        // A mistyped year would otherwise produce thousands of empty days
+        let last = if (last - first).num_days() > MAX_DAYS { first + Duration::days(MAX_DAYS) } else { last };
+        let mut date = first;
+        while date <= last {
+            days.push(Day {
+                date,
+                destination: destination_on(&destinations, date),
+                flights: Vec::new(),
+                travel: Vec::new(),
+                lodging: Vec::new(),
+                places: Vec::new(),
+            });
+            date = date + Duration::days(1);
+        }
+    }
+
+    let mut unscheduled: Vec<(String, Entry)> = Vec::new();
+    for (kind, booking) in &bookings {
+        match kind {
+            BookingKind::Flight => {
+                let entry = Entry::Booking(booking.clone());
+                if !place_on_day("Flights", &entry, &mut days) {
+                    unscheduled.push(("Flights".to_string(), entry));
+                }
+            }
+            BookingKind::Travel => {
+                let entry = Entry::Booking(booking.clone());
+                if !place_on_day("Travel", &entry, &mut days) {
+                    unscheduled.push(("Travel".to_string(), entry));
+                }
+            }
+            BookingKind::Accommodation => {
+                // Lodging is listed on every night between check-in and check-out
+                let check_in = util::parse_date(&booking.start);
+                let check_out = util::parse_date(&booking.end).or(check_in);
+                let mut placed = false;
+                if let (Some(check_in), Some(check_out)) = (check_in, check_out) {
+                    for day in days.iter_mut() {
+                        if check_in <= day.date && (day.date < check_out || day.date == check_in) {
+                            day.lodging.push(Entry::Booking(booking.clone()));
+                            placed = true;
+                        }
+                    }
+                }
+                if !placed {
+                    unscheduled.push(("Lodging".to_string(), Entry::Booking(booking.clone())));
+                }
+            }
+        }
+    }
+
+    for place in places {
+        let entry = Entry::Place(place);
+        if !place_on_day("Places", &entry, &mut days) {
+            unscheduled.push(("Places".to_string(), entry));
+        }
+    }
+
+    Some(Plan {
+        title: title.to_string(),
+        start_date,
+        end_date,
+        days,
+        unscheduled,
+        bookings,
+    })
+}
+
+/// Describes an entry on one line, e.g. "LH 123 (2025-03-12 10:15 - 12:30) at FRA, confirmation ABC123"
+/// or "Louvre (2025-03-12 10:00) at Rue de Rivoli, costs 17".
+pub fn describe(entry: &Entry, redact_codes: bool) -> String {
+    match entry {
+        Entry::Booking(booking) => describe_booking(booking, redact_codes),
+        Entry::Place(place) => describe_place(place),
+    }
+}
+
+fn describe_place(place: &Place) -> String {
+    let mut text = place.name.clone();
+
+    let when = format!("{} {}", place.date, place.time);
+    if !when.trim().is_empty() {
+        text.push_str(&format!(" ({})", when.trim()));
+    }
+    if !place.address.is_empty() {
+        text.push_str(&format!(" at {}", place.address));
+    }
+    if !place.cost.is_empty() {
+        text.push_str(&format!(", costs {}", place.cost));
+    }
+    if !place.notes.is_empty() {
+        text.push_str(&format!(", {}", place.notes));
+    }
+
+    text
+}
+
+fn describe_booking(booking: &Booking, redact_codes: bool) -> String {
+    let mut text = booking.title.clone();
+
+    if !booking.start.is_empty() || !booking.end.is_empty() {
+        text.push_str(&format!(" ({} - {})", booking.start, booking.end));
+    }
+    if !booking.location.is_empty() {
+        text.push_str(&format!(" at {}", booking.location));
+    }
+    if !booking.confirmation.is_empty() {
+        text.push_str(&format!(", confirmation {}", confirmation(booking, redact_codes)));
+    }
+
+    text
+}
+
+/// Returns the sections of a day as (heading, entries) pairs, leaving out empty ones.
+pub fn day_sections(day: &Day) -> Vec<(&'static str, &Vec<Entry>)> {
+    vec![
+        ("Flights", &day.flights),
+        ("Travel", &day.travel),
+        ("Lodging", &day.lodging),
+        ("Places", &day.places),
+    ]
+    .into_iter()
+    .filter(|(_, entries)| !entries.is_empty())
+    .collect()
+}
+
+/// Formats the heading of a day, e.g. "Wednesday, 12 March 2025 - Japan".
+pub fn day_heading(day: &Day) -> String {
+    let date = day.date.format("%A, %d %B %Y").to_string();
+    match &day.destination {
+        Some(destination) => format!("{} - {}", date, destination),
+        None => date,
+    }
+}
+
+/// Renders a plan as a Markdown document.
+pub fn to_markdown(plan: &Plan, redact_codes: bool) -> String {
+    let mut out = String::new();
+
+    out.push_str(&format!("# {}\n\n", plan.title));
+    out.push_str(&format!("**Dates:** {} to {}\n\n", plan.start_date, plan.end_date));
+
+    for day in &plan.days {
+        out.push_str(&format!("## {}\n\n", day_heading(day)));
+
+        let sections = day_sections(day);
+        if sections.is_empty() {
+            out.push_str("_Nothing planned._\n\n");
+        }
+        for (heading, entries) in sections {
+            out.push_str(&format!("**{}**\n\n", heading));
+            for entry in entries {
+                out.push_str(&format!("- {}\n", describe(entry, redact_codes)));
+            }
+            out.push('\n');
+        }
+    }
+
+    if !plan.unscheduled.is_empty() {
+        out.push_str("## Unscheduled\n\n");
+        for (section, entry) in &plan.unscheduled {
+            out.push_str(&format!("- {}: {}\n", section, describe(entry, redact_codes)));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Escapes text for safe inclusion in HTML.
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Renders a plan as a self-contained HTML document with inline styling.
+pub fn to_html(plan: &Plan, redact_codes: bool) -> String {
+    let mut body = String::new();
+
+    body.push_str(&format!("<h1>{}</h1>\n", escape_html(&plan.title)));
+    body.push_str(&format!(
+        "<p class=\"dates\"><strong>Dates:</strong> {} to {}</p>\n",
+        escape_html(&plan.start_date),
+        escape_html(&plan.end_date)
+    ));
+
+    for day in &plan.days {
+        body.push_str("<section class=\"day\">\n");
+        body.push_str(&format!("<h2>{}</h2>\n", escape_html(&day_heading(day))));
+
+        let sections = day_sections(day);
+        if sections.is_empty() {
+            body.push_str("<p class=\"empty\">Nothing planned.</p>\n");
+        }
+        for (heading, entries) in sections {
+            body.push_str(&format!("<h3>{}</h3>\n<ul>\n", heading));
+            for entry in entries {
+                body.push_str(&format!("<li>{}</li>\n", escape_html(&describe(entry, redact_codes))));
+            }
+            body.push_str("</ul>\n");
+        }
+        body.push_str("</section>\n");
+    }
+
+    if !plan.unscheduled.is_empty() {
+        body.push_str("<section class=\"day\">\n<h2>Unscheduled</h2>\n<ul>\n");
+        for (section, entry) in &plan.unscheduled {
+            body.push_str(&format!(
+                "<li>{}: {}</li>\n",
+                section,
+                escape_html(&describe(entry, redact_codes))
+            ));
+        }
+        body.push_str("</ul>\n</section>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&plan.title),
+        HTML_STYLE,
+        body
+    )
+}
+
+/// Inline stylesheet so the exported HTML needs no other files.
+const HTML_STYLE: &str = "body { font-family: sans-serif; max-width: 50em; margin: 2em auto; color: #222; }
+h1 { border-bottom: 2px solid #222; }
+.day { page-break-inside: avoid; margin-bottom: 1.5em; }
+h2 { font-size: 1.2em; background: #eee; padding: 0.3em 0.5em; }
+h3 { font-size: 1em; margin-bottom: 0.2em; }
+.empty { color: #888; font-style: italic; }
+";
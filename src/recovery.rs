@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use chrono::prelude::*;
+use gtk::prelude::*;
+use gtk::{ApplicationWindow, ButtonsType, DialogFlags, MessageDialog, MessageType, ResponseType};
+use serde::{Deserialize, Serialize};
+
+use crate::database::UnsavedDraft;
+use crate::globals;
+use crate::logging::Level;
+use crate::routes::{self, Navigator, Route};
+
+/// Seconds between autosaves of the drafts being edited.
+const AUTOSAVE_SECONDS: u32 = 10;
+
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// The unsaved drafts written to the recovery file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Recovery {
+    /// When the file was written, RFC 3339.
+    pub saved_at: String,
+    /// Session ID of the run that wrote it.
+    pub session_id: i64,
+    /// Every draft with unsaved changes, the one being edited first.
+    pub drafts: Vec<UnsavedDraft>,
+    /// The drafts, also in `drafts`, left by earlier runs and neither restored nor discarded yet.
+    #[serde(default)]
+    pub earlier: Vec<UnsavedDraft>,
+}
+
+/// Recovery file, kept next to the database so each database has its own, e.g. `database.json.recovery`.
+pub fn recovery_path() -> PathBuf {
+    let database = globals::get_database();
+    let path = database.lock().unwrap().get_path();
+    PathBuf::from(format!("{}.recovery", path))
+}
+
+/// Reads the drafts left in the recovery file, if any.
+pub fn read() -> Option<Recovery> {
+    let content = fs::read_to_string(recovery_path()).ok()?;
+    match serde_json::from_str::<Recovery>(&content) {
+        Ok(recovery) if recovery.drafts.iter().any(|draft| !draft.session.is_empty()) => Some(recovery),
+        Ok(_) => None,
+        Err(e) => {
+            log(Level::Error, &format!("Recovery file could not be read: {}", e));
+            None
+        }
+    }
+}
+
+/// Deletes the recovery file.
+pub fn discard() {
+    match fs::remove_file(recovery_path()) {
+        Ok(()) => log(Level::Info, "Recovered drafts discarded."),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => log(Level::Error, &format!("Recovery file could not be removed: {}", e)),
+    }
+}
+
+/// Writes the drafts being edited to the recovery file, or removes this run's file once there is
+/// nothing unsaved, e.g. after the drafts were saved. Skips the write if the drafts are unchanged.
+/// Drafts an earlier run left that were neither restored nor discarded, e.g. because the recovery
+/// dialog was closed, are kept in the file next to this run's until the user decides.
+pub fn autosave() {
+    let (mut drafts, session_id) = {
+        let database = globals::get_database();
+        let db_instance = database.lock().unwrap();
+        (db_instance.unsaved_drafts(), db_instance.session_id())
+    };
+    let previous = read();
+    let ours = previous.as_ref().map_or(false, |recovery| recovery.session_id == session_id);
+
+    // An earlier run's file is left as it is until something new is typed
+    if drafts.is_empty() && !ours {
+        return;
+    }
+
+    let earlier = match previous.as_ref() {
+        Some(recovery) if ours => recovery.earlier.clone(),
+        Some(recovery) => recovery.drafts.clone(),
+        None => Vec::new(),
+    };
+    drafts.extend(earlier.iter().cloned());
+
+    if drafts.is_empty() {
+        discard();
+        return;
+    }
+    if previous.map_or(false, |recovery| ours && recovery.drafts == drafts) {
+        return;
+    }
+
+    write(&Recovery { saved_at: Local::now().to_rfc3339(), session_id, drafts, earlier });
+}
+
+/// Replaces the recovery file.
+fn write(recovery: &Recovery) {
+    let result = serde_json::to_string_pretty(recovery)
+        .map_err(io::Error::from)
+        .and_then(|json| fs::write(recovery_path(), json));
+    if let Err(e) = result {
+        log(Level::Error, &format!("Drafts could not be saved: {}", e));
+    }
+}
+
+/// Autosaves the drafts being edited every few seconds for as long as the app runs.
+pub fn start_autosave() {
+    gtk::glib::timeout_add_seconds_local(AUTOSAVE_SECONDS, || {
+        autosave();
+        gtk::glib::Continue(true)
+    });
+}
+
+/// Offers to restore the drafts left by a previous run that quit or crashed before saving.
+/// Restoring opens the first on the CREATE screen and keeps the others on the DRAFTS screen;
+/// discarding deletes them. Closing the dialog keeps them to be offered again on the next start.
+pub fn offer_recovery(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    let recovery = match read() {
+        Some(recovery) => recovery,
+        None => return,
+    };
+
+    let titles: Vec<String> = recovery
+        .drafts
+        .iter()
+        .filter(|draft| !draft.session.is_empty())
+        .map(|draft| match draft.session.fields.get("title").filter(|t| !t.trim().is_empty()) {
+            Some(title) => format!("'{}'", title),
+            None => "an untitled itinerary".to_string(),
+        })
+        .collect();
+    let saved_at = DateTime::parse_from_rfc3339(&recovery.saved_at)
+        .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| recovery.saved_at.clone());
+    let message = match titles.len() {
+        1 => format!("An unsaved draft of {} from {} was found.", titles[0], saved_at),
+        count => format!("{} unsaved drafts from {} were found: {}.", count, saved_at, titles.join(", ")),
+    };
+
+    let dialog = MessageDialog::new(
+        Some(window),
+        DialogFlags::MODAL | DialogFlags::DESTROY_WITH_PARENT,
+        MessageType::Question,
+        ButtonsType::None,
+        &message,
+    );
+    dialog.set_secondary_text(Some("Restore them to keep editing, or discard them."));
+    dialog.add_button("Discard", ResponseType::Reject);
+    dialog.add_button("Restore", ResponseType::Accept);
+    dialog.set_default_response(ResponseType::Accept);
+
+    dialog.connect_response({
+        let window = window.clone();
+        let navigator = navigator.clone();
+        move |dialog, response| {
+            dialog.close();
+            if response == ResponseType::Accept {
+                let (drafts, session_id) = {
+                    let database = globals::get_database();
+                    let mut db_instance = database.lock().unwrap();
+                    db_instance.restore_drafts(&recovery.drafts);
+                    (db_instance.unsaved_drafts(), db_instance.session_id())
+                };
+                // The restored drafts belong to this run now, so they are no longer kept as earlier ones
+                write(&Recovery { saved_at: Local::now().to_rfc3339(), session_id, drafts, earlier: Vec::new() });
+                log(Level::Info, "Drafts restored.");
+                routes::route_jump(&window, &navigator, Route::Create { itinerary: None });
+            } else if response == ResponseType::Reject {
+                discard();
+            }
+        }
+    });
+
+    dialog.show_all();
+}
@@ -4,81 +4,206 @@ use gtk::ApplicationWindow;
 
 use crate::create;
 use crate::debug;
+use crate::drafts;
 use crate::destinations;
 use crate::flights;
 use crate::globals;
 use crate::home;
 use crate::places;
 use crate::util;
+use crate::validation;
 use crate::open;
+use crate::import;
+use crate::export;
+use crate::universals::Screens;
+use crate::logging::Level;
 
-/// Routes to a specified screen by clearing the window and updating the current screen state.
-fn route(window: &ApplicationWindow, current_screen: &Rc<RefCell<String>>, new_screen: &str) {
-    util::clear_window(window);
-    util::set_current_screen(current_screen, new_screen);
-    
-    match new_screen {
-        "HOME" => home::view(window),
-        "FLIGHTS" => flights::view(window),
-        "DESTINATIONS" => destinations::view(window),
-        "PLACES" => places::view(window),
-        "DEBUG" => debug::view(window),
-        "CREATE" => create::view(window),
-        "OPEN" => open::view(window),
-        _ => globals::add_to_debug_log("ERROR in 'routes.rs': 'new_screen' matched no specified route."),
+/// Logs messages to the application's debug log, tagged with this module.
+fn log(level: Level, message: &str) {
+    globals::add_to_debug_log(level, module_path!(), message);
+}
+
+/// A screen of the app, together with what it was opened for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Route {
+    Home,
+    Open,
+    /// Edits the itinerary with the given title, or the one already in session storage when `None`.
+    Create { itinerary: Option<String> },
+    Destinations,
+    Flights,
+    Places,
+    Import,
+    /// Exports the itinerary with the given title, or the last one exported when `None`.
+    Export { itinerary: Option<String> },
+    Debug,
+    /// Lists the drafts being edited to switch between them.
+    Drafts,
+}
+
+impl Route {
+    /// The screen this route shows, used for key bindings and the nav bar.
+    pub fn screen(&self) -> Screens {
+        match self {
+            Route::Home => Screens::Home,
+            Route::Open => Screens::Open,
+            Route::Create { .. } => Screens::Create,
+            Route::Destinations => Screens::Destinations,
+            Route::Flights => Screens::Flights,
+            Route::Places => Screens::Places,
+            Route::Import => Screens::Import,
+            Route::Export { .. } => Screens::Export,
+            Route::Debug => Screens::Debug,
+            Route::Drafts => Screens::Drafts,
+        }
     }
 }
 
-/// Determines which screen to return to based on the current screen state.
-/// Ensures logical navigation backward within the application.
-pub fn route_back(window: &ApplicationWindow, current_screen: &Rc<RefCell<String>>) {
-    let screen = current_screen.borrow().clone();
-    match screen.as_str() {
-        "HOME" => route(window, current_screen, "HOME"), // No back navigation from HOME
-        "FLIGHTS" => route(window, current_screen, "CREATE"),
-        "DESTINATIONS" => route(window, current_screen, "CREATE"),
-        "PLACES" => route(window, current_screen, "CREATE"),
-        "CREATE" => route(window, current_screen, "HOME"),
-        "DEBUG" => route(window, current_screen, "HOME"),
-        "OPEN" => route(window, current_screen, "HOME"),
-        _ => globals::add_to_debug_log("ERROR in 'routes.rs' ln 61: 'current_screen' in route_back matched no specified route."),
+/// Whether `to` can be opened from `from`.
+/// Anything else is ignored, so a stray key press cannot jump across the app.
+fn is_reachable(from: Screens, to: Screens) -> bool {
+    match from {
+        Screens::Home => matches!(to, Screens::Create | Screens::Debug | Screens::Open | Screens::Export | Screens::Drafts),
+        Screens::Create => matches!(
+            to,
+            Screens::Flights
+                | Screens::Destinations
+                | Screens::Places
+                | Screens::Open
+                | Screens::Import
+                | Screens::Export
+                | Screens::Debug
+                | Screens::Drafts
+        ),
+        Screens::Drafts | Screens::Open => matches!(to, Screens::Create),
+        // No forward navigation from the other screens currently
+        _ => false,
     }
 }
 
-/// Determines the next screen to navigate to based on the current state.
-/// If no valid forward navigation is available, it safely does nothing.
-pub fn route_forward(window: &ApplicationWindow, current_screen: &Rc<RefCell<String>>, new_screen: &str) {
-    let screen = current_screen.borrow().clone();
-    match screen.as_str() {
-        "HOME" => match new_screen {
-            "CREATE" => route(window, current_screen, "CREATE"),
-            "DEBUG" => route(window, current_screen, "DEBUG"),
-            "OPEN" => route(window, current_screen, "OPEN"),
-            _ => {}
-        },
-        "FLIGHTS" => {
-            // No forward navigation available from FLIGHTS currently.
-            // Placeholder for future functionality.
+/// The current route and the routes that led to it.
+pub struct Navigator {
+    current: Route,
+    history: Vec<Route>,
+}
+
+impl Navigator {
+    pub fn new() -> Navigator {
+        Navigator { current: Route::Home, history: Vec::new() }
+    }
+
+    pub fn screen(&self) -> Screens {
+        self.current.screen()
+    }
+
+    pub fn current(&self) -> &Route {
+        &self.current
+    }
+
+    /// The routes that led to the current one, oldest first.
+    pub fn history(&self) -> &[Route] {
+        &self.history
+    }
+}
+
+impl Default for Navigator {
+    fn default() -> Navigator {
+        Navigator::new()
+    }
+}
+
+/// Prepares session storage for the route's parameters before its view reads it.
+fn prepare(route: &Route) {
+    let database = globals::get_database();
+    let mut db_instance = database.lock().unwrap();
+
+    match route {
+        Route::Create { itinerary: Some(title) } => {
+            let loaded = db_instance.get_session_storage().get("title") == Some(title);
+            if !loaded && !db_instance.load_itinerary_into_session_storage(title) {
+                log(Level::Error, &format!("No itinerary named '{}' to open.", title));
+            }
         }
-        "DESTINATIONS" => {
-            // No forward navigation available from DESTINATIONS currently.
-            // Placeholder for future functionality.
+        Route::Export { itinerary: Some(title) } => db_instance.set_kv_session_storage("export_itinerary", title),
+        _ => {}
+    }
+}
+
+/// Shows a route by clearing the window and building its view.
+fn show(window: &ApplicationWindow, route: &Route) {
+    prepare(route);
+    build(window, route);
+}
+
+/// Clears the window and builds the route's view from the current session storage.
+fn build(window: &ApplicationWindow, route: &Route) {
+    util::clear_window(window);
+    validation::clear_current_form();
+
+    match route {
+        Route::Home => home::view(window),
+        Route::Flights => flights::view(window),
+        Route::Destinations => destinations::view(window),
+        Route::Places => places::view(window),
+        Route::Debug => debug::view(window),
+        Route::Create { .. } => create::view(window),
+        Route::Open => open::view(window),
+        Route::Import => import::view(window),
+        Route::Export { .. } => export::view(window),
+        Route::Drafts => drafts::view(window),
+    }
+}
+
+/// Returns to the route we came from. Does nothing on the first screen.
+/// The route is rebuilt from session storage as left, without loading its parameters again,
+/// so e.g. a renamed itinerary keeps its edits instead of being reloaded from the database.
+pub fn route_back(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    let previous = {
+        let mut navigator = navigator.borrow_mut();
+        match navigator.history.pop() {
+            Some(previous) => {
+                navigator.current = previous.clone();
+                previous
+            }
+            None => return,
         }
-        "PLACES" => {
-            // No forward navigation available from PLACES currently.
-            // Placeholder for future functionality.
+    };
+
+    build(window, &previous);
+}
+
+/// Opens a route from the current one, remembering the current one for `route_back`.
+/// Routes that cannot be reached from the current screen are ignored.
+pub fn route_forward(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>, route: Route) {
+    {
+        let mut navigator = navigator.borrow_mut();
+        if !is_reachable(navigator.screen(), route.screen()) {
+            return;
         }
-        "CREATE" => match new_screen {
-            "FLIGHTS" => route(window, current_screen, "FLIGHTS"),
-            "DESTINATIONS" => route(window, current_screen, "DESTINATIONS"),
-            "PLACES" => route(window, current_screen, "PLACES"),
-            "OPEN" => route(window, current_screen, "OPEN"),
-            _ => {}
-        },
-        "DEBUG" => {
-            // No forward navigation available from DEBUG currently.
-            // Placeholder for future functionality.
+        let previous = std::mem::replace(&mut navigator.current, route.clone());
+        navigator.history.push(previous);
+    }
+
+    show(window, &route);
+}
+
+/// Opens a route from any screen, as if it had been reached from HOME.
+/// The history is reset to HOME, so Back leads there. Used by the command palette.
+pub fn route_jump(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>, route: Route) {
+    {
+        let mut navigator = navigator.borrow_mut();
+        navigator.history.clear();
+        if route != Route::Home {
+            navigator.history.push(Route::Home);
         }
-        _ => globals::add_to_debug_log("ERROR in 'routes.rs': 'current_screen' in route_forward matched no specified route."),
+        navigator.current = route.clone();
     }
-}
\ No newline at end of file
+
+    show(window, &route);
+}
+
+/// Rebuilds the current screen, e.g. after an undo changed the values it shows.
+pub fn refresh(window: &ApplicationWindow, navigator: &Rc<RefCell<Navigator>>) {
+    let route = navigator.borrow().current.clone();
+    build(window, &route);
+}
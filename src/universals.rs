@@ -1,12 +1,20 @@
 use gtk::prelude::*;
-use gtk::{TextView, Box as GtkBox, Orientation, WrapMode};
+use gtk::{TextView, Box as GtkBox, Orientation, PolicyType, ScrolledWindow, SelectionMode, WrapMode};
+use gtk::{Button, CheckButton, ComboBoxText, FileChooserAction, FileChooserDialog, ResponseType, SearchEntry, ToggleButton};
+use gtk::{Expander, InfoBar, MessageType};
 use std::cell::RefCell;
+use std::fs;
 use std::rc::Rc;
+use chrono::Local;
+use glib::clone;
 use gtk::glib::timeout_add_local;
 use std::time::Duration;
 use crate::util;
 use crate::globals;
+use crate::keymap;
+use crate::logging::{Level, Record};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Screens {
     Home,
     Create,
@@ -15,84 +23,108 @@ pub enum Screens {
     Flights,
     Places,
     Open,
+    Import,
+    Export,
+    Drafts,
 }
 
-// Adds a text label to the given container, styled as a navbar item
-fn add_nav_text(container: &gtk::Box, text: &str, class: Option<&str>) {
-    if let Some(value) = class {
-    	let label = util::add_text_manual(text, &[0, 0, 0, 0], Some(value));
-    	container.pack_start(&label, false, false, 0);
-    } else {
-    	let label = util::add_text_manual(text, &[0, 0, 0, 0], Some("navbar-item"));
-    	container.pack_start(&label, false, false, 0);
+// Creates a navigation bar with a button for each shortcut bound on the given screen in the keymap registry
+// Each button runs the same action as its key; hints wrap onto more lines when the window is narrow
+fn nav(screen_type: &Screens) -> gtk::FlowBox {
+    let container = gtk::FlowBox::new();
+    container.set_selection_mode(SelectionMode::None);
+    container.set_column_spacing(20);
+    container.set_row_spacing(5);
+    container.set_margin_top(10);
+    container.set_margin_bottom(10);
+    container.set_margin_start(50);
+    container.set_margin_end(50);
+    container.style_context().add_class("navbar");
+
+    let keymap = keymap::current();
+    for binding in keymap.bindings_for(*screen_type) {
+        let hint = format!("{} to {}", binding.chord.display(), binding.action.description());
+        let button = util::add_text_button_manual(&hint, &[0, 0, 0, 0], &[0, 0], Some("navbar-item"), Some(&binding.action.detailed_name()));
+        container.add(&button);
     }
-}
 
-// Creates a navigation bar with contextual shortcuts based on the current screen
-pub fn nav(screen_type: &Screens) -> gtk::Box {
-    let container_outer = gtk::Box::new(gtk::Orientation::Vertical, 5);
-    let container_inner = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-    container_inner.set_margin_top(500);
-    container_inner.set_margin_bottom(5);
-    container_inner.set_margin_start(50);
-    container_inner.set_margin_end(50);
+    // Only the buttons take focus, so Tab walks the nav bar left to right like the rest of the screen
+    for child in container.children() {
+        child.set_can_focus(false);
+    }
 
+    container
+}
 
-    match screen_type {
-        Screens::Home => {
-			add_nav_text(&container_inner, "ESC to quit", None);
-			container_outer.pack_start(&container_inner, false, false, 0);
-        }
-        Screens::Create => {
-			add_nav_text(&container_inner, "ESC to quit", None);
-            add_nav_text(&container_inner, "Shift + 1 to go back", None);
-            add_nav_text(&container_inner, "Shift + 9 to create/save itinerary", None);
-            container_outer.pack_start(&container_inner, false, false, 0);
-        }
-        Screens::Open => {
-        	add_nav_text(&container_inner, "ESC to quit", None);
-			add_nav_text(&container_inner, "Shift + 1 to go back", None);
-			add_nav_text(&container_inner, "Shift + [x] to select", None);
-            container_outer.pack_start(&container_inner, false, false, 0);
+// Lays out a screen and shows it: the content scrolls and reflows with the window size,
+// while the nav bar stays pinned to the bottom and error banners appear above the content
+pub fn page(window: &gtk::ApplicationWindow, content: &GtkBox, screen_type: &Screens) {
+    let layout = GtkBox::new(Orientation::Vertical, 0);
+    let banners = GtkBox::new(Orientation::Vertical, 0);
 
-            let pager_container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-            pager_container.set_margin_top(20);
-		    pager_container.set_margin_bottom(5);
-		    pager_container.set_margin_start(50);
-		    pager_container.set_margin_end(50);
+    content.set_margin_top(40);
+    content.set_margin_bottom(20);
+    content.set_margin_start(50);
+    content.set_margin_end(50);
 
-            add_nav_text(&pager_container, "Shift + 8 to next page", Some("pager")); 
-            add_nav_text(&pager_container, "Shift + 9 to previous page", Some("pager")); 
+    let scroller = ScrolledWindow::builder()
+        .hscrollbar_policy(PolicyType::Automatic)
+        .vscrollbar_policy(PolicyType::Automatic)
+        .build();
+    scroller.add(content);
 
-            container_outer.pack_start(&pager_container, false, false, 0);
+    layout.pack_start(&banners, false, false, 0);
+    layout.pack_start(&scroller, true, true, 0);
+    layout.pack_end(&nav(screen_type), false, false, 0);
 
-        }
-        Screens::Destinations => {
-        	container_inner.set_margin_top(580);
-			add_nav_text(&container_inner, "ESC to quit", None);
-            add_nav_text(&container_inner, "Shift + 1 to go back", None);
-            container_outer.pack_start(&container_inner, false, false, 0);
-
-            let pager_container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
-            pager_container.set_margin_top(20);
-		    pager_container.set_margin_bottom(5);
-		    pager_container.set_margin_start(50);
-		    pager_container.set_margin_end(50);
-
-            add_nav_text(&pager_container, "Shift + 9 to save destination", None);
-            add_nav_text(&pager_container, "Shift + Ctrl + [x] to delete destination", None);
-
-            container_outer.pack_start(&pager_container, false, false, 0);
-        }
+    window.add(&layout);
+    window.show_all();
+}
 
-        _ => {
-        	add_nav_text(&container_inner, "ESC to quit", None);
-            add_nav_text(&container_inner, "Shift + 1 to go back", None);
-            container_outer.pack_start(&container_inner, false, false, 0);
-        }
+// Shows an error in a banner at the top of the current screen, replacing any earlier one
+// The banner says what went wrong in words, explaining known status codes, and keeps the
+// raw error in a collapsed "Details" section; the screen and what was typed into it stay as they are
+pub fn show_error(window: &gtk::ApplicationWindow, summary: &str, details: &str) {
+    let banners = window
+        .child()
+        .and_then(|layout| layout.downcast::<GtkBox>().ok())
+        .and_then(|layout| layout.children().into_iter().next())
+        .and_then(|banners| banners.downcast::<GtkBox>().ok());
+    let banners = match banners {
+        Some(banners) => banners,
+        None => return,
+    };
+    for banner in banners.children() {
+        banners.remove(&banner);
     }
 
-    container_outer
+    let message = match util::explain_status(details) {
+        Some(explanation) => format!("{} {}", summary, explanation),
+        None => summary.to_string(),
+    };
+
+    let bar = InfoBar::new();
+    bar.set_message_type(MessageType::Error);
+    bar.set_show_close_button(true);
+    bar.connect_response(|bar, _| bar.hide());
+
+    let text = GtkBox::new(Orientation::Vertical, 5);
+    let label = util::add_text_manual(&message, &[0, 0, 0, 0], None);
+    label.set_xalign(0.0);
+    label.set_line_wrap(true);
+    text.pack_start(&label, false, false, 0);
+
+    let expander = Expander::new(Some("Details"));
+    let details_label = util::add_text_manual(&format!("{}\nThe DEBUG screen has the full log.", details), &[5, 0, 15, 0], None);
+    details_label.set_xalign(0.0);
+    details_label.set_line_wrap(true);
+    details_label.set_selectable(true);
+    expander.add(&details_label);
+    text.pack_start(&expander, false, false, 0);
+
+    bar.content_area().add(&text);
+    banners.pack_start(&bar, false, false, 0);
+    bar.show_all();
 }
 
 // Applies margin settings to a given widget
@@ -103,9 +135,99 @@ fn set_margins(widget: &impl gtk::prelude::WidgetExt, margins: &[i32]) {
     widget.set_margin_end(margins[3]);
 }
 
+// Which log records the debug console shows
+struct ConsoleFilter {
+    // Least serious level shown
+    level: Level,
+    module: Option<String>,
+    // Shown records contain this text, ignoring case
+    query: String,
+}
+
+impl ConsoleFilter {
+    fn matches(&self, record: &Record) -> bool {
+        record.level <= self.level
+            && self.module.as_ref().map_or(true, |module| *module == record.module)
+            && (self.query.is_empty() || record.line().to_lowercase().contains(&self.query))
+    }
+}
+
+// What the debug console has shown so far
+struct ConsoleState {
+    filter: ConsoleFilter,
+    // Last record looked at, new records are appended after it
+    last_seq: u64,
+    // Records up to this one were cleared from the console
+    cleared_seq: u64,
+    // Modules offered in the module filter
+    modules: Vec<String>,
+}
+
+// Appends records to the console that match the filter, dropping the oldest lines past the in-memory log's size
+fn append_records(console: &TextView, records: &[Record], state: &mut ConsoleState, module_filter: &ComboBoxText) {
+    let buffer = console.buffer().unwrap();
+
+    for record in records {
+        if !state.modules.contains(&record.module) {
+            state.modules.push(record.module.clone());
+            module_filter.append(Some(&record.module), &record.module);
+        }
+        if state.filter.matches(record) {
+            let mut end = buffer.end_iter();
+            let line = if buffer.char_count() == 0 { record.line() } else { format!("\n{}", record.line()) };
+            buffer.insert(&mut end, &line);
+        }
+        state.last_seq = record.seq;
+    }
+
+    let excess = buffer.line_count() - globals::MAX_LOG_RECORDS as i32;
+    if excess > 0 {
+        let (mut start, mut end) = (buffer.start_iter(), buffer.iter_at_line(excess));
+        buffer.delete(&mut start, &mut end);
+    }
+}
+
+// Refills the console from the records since the last clear, e.g. after the filter changed
+fn reload_console(console: &TextView, state: &mut ConsoleState, module_filter: &ComboBoxText) {
+    console.buffer().unwrap().set_text("");
+    let records = globals::get_log_records_since(state.cleared_seq);
+    append_records(console, &records, state, module_filter);
+}
+
+// Asks where to save the lines shown in the console and writes them there
+fn save_console(console: &TextView) {
+    let parent = console.toplevel().and_then(|widget| widget.downcast::<gtk::Window>().ok());
+    let dialog = FileChooserDialog::with_buttons(
+        Some("Save Log"),
+        parent.as_ref(),
+        FileChooserAction::Save,
+        &[("Cancel", ResponseType::Cancel), ("Save", ResponseType::Accept)],
+    );
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name(&format!("itinerary_planner-{}.log", Local::now().format("%Y%m%d-%H%M%S")));
+
+    let buffer = console.buffer().unwrap();
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            if let Some(path) = dialog.filename() {
+                let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false).map(|text| text.to_string()).unwrap_or_default();
+                match fs::write(&path, format!("{}\n", text)) {
+                    Ok(()) => globals::add_to_debug_log(Level::Info, module_path!(), &format!("Log saved to {}", path.display())),
+                    Err(e) => globals::add_to_debug_log(Level::Error, module_path!(), &format!("Log could not be saved to {}: {}", path.display(), e)),
+                }
+            }
+        }
+        dialog.close();
+    });
+
+    dialog.show_all();
+}
+
 // Creates a debug console that displays log messages in a non-editable text area
+// New records are appended as they are logged; the toolbar filters them by level, module and text,
+// pauses or stops following them, clears the console and saves what it shows to a file
 pub fn debug_console(size: &[i32], margins: &[i32], class: Option<&str>) -> GtkBox {
-    let container = GtkBox::new(Orientation::Horizontal, 5);
+    let container = GtkBox::new(Orientation::Vertical, 5);
     set_margins(&container, margins);
 
     let console = TextView::new();
@@ -116,25 +238,124 @@ pub fn debug_console(size: &[i32], margins: &[i32], class: Option<&str>) -> GtkB
     console.set_editable(false); // Prevents user input
     console.set_cursor_visible(false);
     console.set_wrap_mode(WrapMode::Word); // Enables word wrapping
-    console.set_size_request(size[0], size[1]);
+    console.set_hexpand(true);
+    console.set_vexpand(true);
+
+    // The console scrolls on its own, so it can follow new records while the screen stays put
+    let scroller = ScrolledWindow::builder()
+        .hscrollbar_policy(PolicyType::Never)
+        .vscrollbar_policy(PolicyType::Automatic)
+        .build();
+    scroller.set_size_request(size[0], size[1]); // Minimum size, the console grows with the window
+    scroller.set_vexpand(true);
+    scroller.add(&console);
+
+    // Toolbar
+    let toolbar = GtkBox::new(Orientation::Horizontal, 10);
+    let level_filter = ComboBoxText::new();
+    for level in Level::ALL.iter() {
+        let label = if *level == Level::Debug { "All levels".to_string() } else { format!("{} and above", level.name()) };
+        level_filter.append(Some(level.name()), &label);
+    }
+    level_filter.set_active_id(Some(Level::Debug.name()));
+    let module_filter = ComboBoxText::new();
+    module_filter.append(Some(""), "All modules");
+    module_filter.set_active_id(Some(""));
+    let search = SearchEntry::new();
+    search.set_placeholder_text(Some("Search log"));
+    search.set_hexpand(true);
+    let pause = ToggleButton::with_label("Pause");
+    let follow = CheckButton::with_label("Auto-scroll");
+    follow.set_active(true);
+    let clear = Button::with_label("Clear");
+    let save = Button::with_label("Save...");
+
+    toolbar.pack_start(&level_filter, false, false, 0);
+    toolbar.pack_start(&module_filter, false, false, 0);
+    toolbar.pack_start(&search, true, true, 0);
+    toolbar.pack_start(&pause, false, false, 0);
+    toolbar.pack_start(&follow, false, false, 0);
+    toolbar.pack_start(&clear, false, false, 0);
+    toolbar.pack_start(&save, false, false, 0);
+
+    let state = Rc::new(RefCell::new(ConsoleState {
+        filter: ConsoleFilter { level: Level::Debug, module: None, query: String::new() },
+        last_seq: 0,
+        cleared_seq: 0,
+        modules: Vec::new(),
+    }));
+
+    // Scrolls to the newest record if auto-scroll is on
+    let scroll_to_end = clone!(@weak console, @weak follow => move || {
+        if follow.is_active() {
+            let buffer = console.buffer().unwrap();
+            let mark = buffer.create_mark(None, &buffer.end_iter(), false);
+            console.scroll_mark_onscreen(&mark);
+            buffer.delete_mark(&mark);
+        }
+    });
 
     // Load initial logs into the text buffer
-    let buffer = console.buffer().unwrap();
-    let log = globals::get_debug_log().join("\n");
-    buffer.set_text(&log);
-
-    // Store the console in Rc<RefCell<>> to allow dynamic updates
-    let console_ref = Rc::new(RefCell::new(console.clone()));
-
-    // Periodically update the console with new log data
-    let console_clone = console_ref.clone();
-    timeout_add_local(Duration::from_millis(500), move || {
-        let log = globals::get_debug_log().join("\n");
-        let buffer = console_clone.borrow().buffer().unwrap();
-        buffer.set_text(&log);
+    reload_console(&console, &mut state.borrow_mut(), &module_filter);
+
+    level_filter.connect_changed(clone!(@weak console, @weak module_filter, @strong state => move |level_filter| {
+        let level = Level::ALL.iter().cloned().find(|level| level_filter.active_id().as_deref() == Some(level.name()));
+        let mut state = state.borrow_mut();
+        state.filter.level = level.unwrap_or(Level::Debug);
+        reload_console(&console, &mut state, &module_filter);
+    }));
+
+    module_filter.connect_changed(clone!(@weak console, @strong state => move |module_filter| {
+        let module = module_filter.active_id().map(|id| id.to_string()).filter(|id| !id.is_empty());
+        let mut state = state.borrow_mut();
+        if state.filter.module != module {
+            state.filter.module = module;
+            reload_console(&console, &mut state, module_filter);
+        }
+    }));
+
+    search.connect_search_changed(clone!(@weak console, @weak module_filter, @strong state => move |search| {
+        let mut state = state.borrow_mut();
+        state.filter.query = search.text().to_lowercase();
+        reload_console(&console, &mut state, &module_filter);
+    }));
+
+    // Catches up on what was logged while paused
+    pause.connect_toggled(clone!(@weak console, @weak module_filter, @strong state, @strong scroll_to_end => move |pause| {
+        if !pause.is_active() {
+            let records = globals::get_log_records_since(state.borrow().last_seq);
+            append_records(&console, &records, &mut state.borrow_mut(), &module_filter);
+            scroll_to_end();
+        }
+    }));
+
+    follow.connect_toggled(clone!(@strong scroll_to_end => move |_| scroll_to_end()));
+
+    // Only the console is cleared, the records stay in the log and its file
+    clear.connect_clicked(clone!(@weak console, @strong state => move |_| {
+        let mut state = state.borrow_mut();
+        state.cleared_seq = state.last_seq;
+        console.buffer().unwrap().set_text("");
+    }));
+
+    save.connect_clicked(clone!(@weak console => move |_| save_console(&console)));
+
+    // Periodically append records logged since the last update
+    // Stops once the console is gone, e.g. after leaving the DEBUG screen
+    timeout_add_local(Duration::from_millis(500), clone!(@weak console, @weak module_filter, @weak pause, @strong state, @strong scroll_to_end => @default-return gtk::glib::Continue(false), move || {
+        if pause.is_active() {
+            return gtk::glib::Continue(true);
+        }
+
+        let records = globals::get_log_records_since(state.borrow().last_seq);
+        if !records.is_empty() {
+            append_records(&console, &records, &mut state.borrow_mut(), &module_filter);
+            scroll_to_end();
+        }
         gtk::glib::Continue(true) // Ensures the update loop continues
-    });
+    }));
 
-    container.pack_start(&*console_ref.borrow(), false, false, 0);
+    container.pack_start(&toolbar, false, false, 0);
+    container.pack_start(&scroller, true, true, 0);
     container
 }
@@ -1,11 +1,13 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
-use std::rc::Rc;
 
+use chrono::NaiveDate;
 use glib::clone;
 use gtk::prelude::*;
 use gtk::{CssProvider, Entry, Label};
 
+use crate::globals;
+use crate::logging::Level;
+
 // Connects a function to an entry's "changed" event (triggered when the text changes)
 pub fn on_change<F>(entry: gtk::Entry, function: F)
 where
@@ -24,12 +26,12 @@ fn set_margins(widget: &impl gtk::prelude::WidgetExt, margin: &[i32]) {
     widget.set_margin_end(margin[3]);
 }
 
-// Adds a Label with text to a Fixed container, applies margins, and optional class
+// Adds a Label with text to a vertical Box container, applies margins, and optional class
 pub fn add_text(
     text: &str,
     margin: &[i32],
     pos: &[f32],
-    window: &gtk::Fixed,
+    container: &gtk::Box,
     class: Option<&str>,
 ) {
     let label = Label::new(Some(text));
@@ -42,7 +44,7 @@ pub fn add_text(
         label.style_context().add_class(value);
     }
 
-    window.add(&label);
+    container.pack_start(&label, false, false, 0);
 }
 
 // Returns a Label with text, applying margins and optional class (without adding to window)
@@ -62,6 +64,15 @@ pub fn add_text_manual(
     label
 }
 
+// Capitalizes the first letter of a phrase, e.g. "go back" -> "Go back"
+pub fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
 // Formats a HashMap into a string with "key: value" pairs separated by newlines
 #[allow(dead_code)]
 pub fn format_hashmap(map: &HashMap<String, String>) -> String {
@@ -72,15 +83,22 @@ pub fn format_hashmap(map: &HashMap<String, String>) -> String {
 }
 
 // Creates a Box containing a Button with text, applies margins, size, and optional class
-#[allow(dead_code)]
+// The button runs the given window action when clicked, e.g. "win.save_itinerary"
 pub fn add_text_button_manual(
     text: &str,
     margin: &[i32],
     size: &[i32],
     class: Option<&str>,
+    action: Option<&str>,
 ) -> gtk::Box {
     let container = gtk::Box::new(gtk::Orientation::Horizontal, 5);
     let button = gtk::Button::with_label(text);
+    if let Some(action) = action {
+        button.set_detailed_action_name(action);
+    }
+    if let Some(label) = button.child().and_then(|child| child.downcast::<Label>().ok()) {
+        label.set_xalign(0.0);
+    }
 
     set_margins(&button, margin);
     button.set_size_request(size[0], size[1]);
@@ -140,15 +158,28 @@ pub fn find_entry_in_box(box_container: &gtk::Box) -> Entry {
 }
 
 // Loads a CSS file from the specified path and returns a CssProvider
-// If the file fails to load, an error message is printed
+// If the file fails to load, the error is logged and the default styling is used
 pub fn load_css(path: &str) -> gtk::CssProvider {
     let provider = CssProvider::new();
-    if provider.load_from_path(path).is_err() {
-        eprintln!("Failed to load CSS file: {}", path);
+    if let Err(e) = provider.load_from_path(path) {
+        globals::add_to_debug_log(Level::Error, module_path!(), &format!("Failed to load CSS file {}: {}", path, e));
     }
     provider
 }
 
+// Explains a status code returned by the database, import and export functions in words for the user
+// Other codes and I/O errors give None, the caller's own message has to do for them
+pub fn explain_status(status: &str) -> Option<&'static str> {
+    match status {
+        "ERROR_NO_TITLE" => Some("Give the itinerary a title first."),
+        "ERROR_NO_ITINERARY" => Some("There is no saved itinerary with that title."),
+        "ERROR_ALREADY_EXISTS" => Some("An itinerary with that title already exists."),
+        "ERROR_UNKNOWN_FORMAT" => Some("One of the formats isn't supported, use the formats listed on the screen."),
+        "ERROR_INVALID_ROWS" => Some("Some rows of the file are invalid, each one is listed in the log."),
+        _ => None,
+    }
+}
+
 // Removes all child widgets from the given window and redraws it
 pub fn clear_window(window: &gtk::ApplicationWindow) {
     for child in window.children() {
@@ -157,10 +188,27 @@ pub fn clear_window(window: &gtk::ApplicationWindow) {
     window.queue_draw();
 }
 
-// Updates the current screen variable to a new screen identifier
-pub fn set_current_screen(
-    screen_var: &Rc<RefCell<String>>,
-    new_screen_var: &str,
-) {
-    *screen_var.borrow_mut() = new_screen_var.to_string();
-}
\ No newline at end of file
+// Parses a date typed by the user, trying the configured date format first,
+// then the other formats used across the app
+// Only the leading date is read, so "2025-03-12 10:15" parses as 2025-03-12
+// The configured format may contain spaces, e.g. "%d %m %Y", so it reads as many words as it has
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    let configured = globals::get_config().date_format;
+    let configured_words = configured.split_whitespace().count().max(1);
+    let configured_part = text.split_whitespace().take(configured_words).collect::<Vec<_>>().join(" ");
+    if let Ok(date) = NaiveDate::parse_from_str(&configured_part, &configured) {
+        return Some(date);
+    }
+
+    let formats = ["%Y-%m-%d", "%d/%m/%Y", "%d.%m.%Y", "%d-%m-%Y"];
+    let date_part = text.trim().split_whitespace().next()?;
+
+    formats
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date_part, format).ok())
+}
+
+// Formats a date with the configured date format
+pub fn format_date(date: NaiveDate) -> String {
+    date.format(&globals::get_config().date_format).to_string()
+}
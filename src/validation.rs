@@ -0,0 +1,351 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use chrono::{Local, NaiveTime};
+use gtk::prelude::*;
+use gtk::{Entry, Label, Orientation};
+
+use crate::util;
+
+/// Style class set on invalid entries and on the message under them.
+const ERROR_CLASS: &str = "error";
+
+/// A check on the text typed into a field. Apart from `Required`, rules accept an empty field.
+#[derive(Clone)]
+pub enum Rule {
+    Required,
+    /// A date in the configured format or one of the other formats the app reads.
+    Date,
+    /// A time of day, e.g. "14:30".
+    Time,
+    /// A three letter IATA airport code, e.g. "LHR".
+    AirportCode,
+    /// A money amount with at most two decimals, e.g. "12.50".
+    Amount,
+    /// Any other check, returning the message to show when the text is invalid.
+    Custom(Rc<dyn Fn(&str) -> Result<(), String>>),
+}
+
+impl Rule {
+    pub fn custom<F: Fn(&str) -> Result<(), String> + 'static>(check: F) -> Rule {
+        Rule::Custom(Rc::new(check))
+    }
+
+    /// Checks the text, returning the message to show under the field if it is invalid.
+    pub fn check(&self, text: &str) -> Result<(), String> {
+        let value = text.trim();
+        if value.is_empty() {
+            return match self {
+                Rule::Required => Err("This field is required.".to_string()),
+                _ => Ok(()),
+            };
+        }
+
+        match self {
+            Rule::Required => Ok(()),
+            Rule::Date => match util::parse_date(value) {
+                Some(_) => Ok(()),
+                None => Err(format!("Enter a date like {}.", util::format_date(Local::now().naive_local().date()))),
+            },
+            Rule::Time => {
+                let valid = ["%H:%M", "%H:%M:%S"].iter().any(|format| NaiveTime::parse_from_str(value, format).is_ok());
+                if valid {
+                    Ok(())
+                } else {
+                    Err("Enter a time like 14:30.".to_string())
+                }
+            }
+            Rule::AirportCode => {
+                if value.len() == 3 && value.chars().all(|c| c.is_ascii_alphabetic()) {
+                    Ok(())
+                } else {
+                    Err("Enter a 3 letter airport code, e.g. LHR.".to_string())
+                }
+            }
+            Rule::Amount => {
+                let decimals = value.split_once('.').map_or(0, |(_, decimals)| decimals.len());
+                // Digits only, since Rust also parses "1e5", "inf" and "NaN" as numbers
+                let digits = value.chars().all(|c| c.is_ascii_digit() || c == '.');
+                match value.parse::<f64>() {
+                    Ok(_) if digits && decimals <= 2 => Ok(()),
+                    _ => Err("Enter an amount like 12.50.".to_string()),
+                }
+            }
+            Rule::Custom(check) => check(value),
+        }
+    }
+}
+
+/// Rule for an end date field: not before the date in the start date entry, when both are dates.
+pub fn not_before(start: &Entry) -> Rule {
+    let start = start.downgrade();
+    Rule::custom(move |end| check_not_before(start.upgrade().map(|start| start.text().to_string()).as_deref(), end))
+}
+
+/// Like `not_before`, for a start date given as text, e.g. as held in session storage.
+pub fn not_before_text(start: &str) -> Rule {
+    let start = start.to_string();
+    Rule::custom(move |end| check_not_before(Some(&start), end))
+}
+
+fn check_not_before(start: Option<&str>, end: &str) -> Result<(), String> {
+    match (start.and_then(util::parse_date), util::parse_date(end)) {
+        (Some(start), Some(end)) if end < start => Err("The end date is before the start date.".to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the text against each rule in turn, returning the message of the first that fails.
+pub fn check_all(rules: &[Rule], text: &str) -> Result<(), String> {
+    rules.iter().try_for_each(|rule| rule.check(text))
+}
+
+/// A labelled entry that checks its text as it is typed and shows what is wrong underneath.
+/// Nothing is flagged until the field was typed into or left, or the form it is in was submitted.
+#[derive(Clone)]
+pub struct Field {
+    /// The label, entry and message, to add to the screen.
+    pub container: gtk::Box,
+    pub entry: Entry,
+    message: Label,
+    rules: Rc<Vec<Rule>>,
+    touched: Rc<Cell<bool>>,
+}
+
+impl Field {
+    /// Builds the field like `util::create_text_entry_manual`, with the rules its text must pass.
+    pub fn new(
+        label_text: &str,
+        size: &[i32],
+        margin: &[i32],
+        class_label: Option<&str>,
+        class_entry: Option<&str>,
+        rules: Vec<Rule>,
+    ) -> Field {
+        let container = gtk::Box::new(Orientation::Vertical, 2);
+        let row = util::create_text_entry_manual(label_text, size, margin, class_label, class_entry);
+        let entry = util::find_entry_in_box(&row);
+
+        // Hidden until there is something wrong; `show_all` on the screen leaves it alone
+        let message = util::add_text_manual("", &[0, 5, margin[2], margin[3]], Some(ERROR_CLASS));
+        message.set_xalign(0.0);
+        message.set_no_show_all(true);
+        message.hide();
+
+        container.pack_start(&row, false, false, 0);
+        container.pack_start(&message, false, false, 0);
+
+        let field = Field { container, entry, message, rules: Rc::new(rules), touched: Rc::new(Cell::new(false)) };
+
+        field.entry.connect_changed({
+            let field = field.downgrade();
+            move |_| {
+                if let Some(field) = field.upgrade() {
+                    field.touched.set(true);
+                    field.validate();
+                }
+            }
+        });
+        field.entry.connect_focus_out_event({
+            let field = field.downgrade();
+            move |_, _| {
+                if let Some(field) = field.upgrade() {
+                    field.touched.set(true);
+                    field.validate();
+                }
+                Inhibit(false)
+            }
+        });
+
+        field
+    }
+
+    /// A reference to the field that doesn't keep its widgets alive, for its own signal handlers.
+    fn downgrade(&self) -> WeakField {
+        WeakField {
+            container: self.container.downgrade(),
+            entry: self.entry.downgrade(),
+            message: self.message.downgrade(),
+            rules: Rc::downgrade(&self.rules),
+            touched: Rc::downgrade(&self.touched),
+        }
+    }
+
+    /// Checks the text. Once the field was touched, marks it and shows the message if it is invalid.
+    pub fn validate(&self) -> bool {
+        let result = check_all(&self.rules, &self.entry.text());
+        if !self.touched.get() {
+            return result.is_ok();
+        }
+
+        match &result {
+            Ok(()) => {
+                self.entry.style_context().remove_class(ERROR_CLASS);
+                self.message.hide();
+            }
+            Err(message) => {
+                self.entry.style_context().add_class(ERROR_CLASS);
+                self.message.set_text(message);
+                self.message.show();
+            }
+        }
+        result.is_ok()
+    }
+
+    /// Checks the text and shows the message if it is invalid, touched or not.
+    pub fn reveal(&self) -> bool {
+        self.touched.set(true);
+        self.validate()
+    }
+}
+
+/// See `Field::downgrade`.
+struct WeakField {
+    container: gtk::glib::WeakRef<gtk::Box>,
+    entry: gtk::glib::WeakRef<Entry>,
+    message: gtk::glib::WeakRef<Label>,
+    rules: Weak<Vec<Rule>>,
+    touched: Weak<Cell<bool>>,
+}
+
+impl WeakField {
+    fn upgrade(&self) -> Option<Field> {
+        Some(Field {
+            container: self.container.upgrade()?,
+            entry: self.entry.upgrade()?,
+            message: self.message.upgrade()?,
+            rules: self.rules.upgrade()?,
+            touched: self.touched.upgrade()?,
+        })
+    }
+}
+
+/// The validated fields of a screen. Typing into one field rechecks the others already touched,
+/// for rules that compare fields, e.g. an end date that must not be before the start date.
+#[derive(Clone, Default)]
+pub struct Form {
+    fields: Rc<RefCell<Vec<Field>>>,
+}
+
+impl Form {
+    pub fn new() -> Form {
+        Form::default()
+    }
+
+    pub fn add(&self, field: &Field) {
+        let fields = Rc::downgrade(&self.fields);
+        let changed = field.entry.clone();
+        field.entry.connect_changed(move |_| {
+            if let Some(fields) = fields.upgrade() {
+                for other in fields.borrow().iter().filter(|other| other.entry != changed) {
+                    other.validate();
+                }
+            }
+        });
+
+        self.fields.borrow_mut().push(field.clone());
+    }
+
+    /// Checks every field, showing the messages of all invalid ones and focusing the first.
+    pub fn validate(&self) -> bool {
+        let fields = self.fields.borrow();
+        let invalid: Vec<&Field> = fields.iter().filter(|field| !field.reveal()).collect();
+        if let Some(first) = invalid.first() {
+            first.entry.grab_focus();
+        }
+        invalid.is_empty()
+    }
+}
+
+// The form on the current screen, if it has one. GTK widgets stay on the main thread,
+// so it is kept per thread rather than in globals.
+thread_local! {
+    static CURRENT_FORM: RefCell<Option<Form>> = RefCell::new(None);
+}
+
+/// Makes `form` the one checked before saving, until the screen changes.
+pub fn set_current_form(form: &Form) {
+    CURRENT_FORM.with(|current| *current.borrow_mut() = Some(form.clone()));
+}
+
+/// Forgets the form of the previous screen. Called by the router before building a screen.
+pub fn clear_current_form() {
+    CURRENT_FORM.with(|current| *current.borrow_mut() = None);
+}
+
+/// Checks the form on the current screen, showing what is wrong. True if there is no form.
+pub fn validate_current_form() -> bool {
+    let form = CURRENT_FORM.with(|current| current.borrow().clone());
+    form.map_or(true, |form| form.validate())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_required_rejects_an_empty_field() {
+        assert!(Rule::Required.check("  ").is_err());
+        assert!(Rule::Required.check("x").is_ok());
+        for rule in [Rule::Date, Rule::Time, Rule::AirportCode, Rule::Amount].iter() {
+            assert!(rule.check("").is_ok());
+        }
+    }
+
+    #[test]
+    fn date_accepts_the_formats_the_app_reads() {
+        for text in ["2025-03-12", "12/03/2025", "12.03.2025", "12-03-2025", " 2025-03-12 10:15 "].iter() {
+            assert!(Rule::Date.check(text).is_ok(), "{}", text);
+        }
+        for text in ["2025-02-30", "12 March", "tomorrow"].iter() {
+            assert!(Rule::Date.check(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn time_accepts_hours_and_minutes_with_optional_seconds() {
+        for text in ["00:00", "23:59", "14:30:15"].iter() {
+            assert!(Rule::Time.check(text).is_ok(), "{}", text);
+        }
+        for text in ["24:00", "12:60", "1430", "2pm"].iter() {
+            assert!(Rule::Time.check(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn airport_code_is_exactly_three_letters() {
+        assert!(Rule::AirportCode.check("LHR").is_ok());
+        assert!(Rule::AirportCode.check(" lhr ").is_ok());
+        for text in ["LH", "LHRX", "LH1", "L-R"].iter() {
+            assert!(Rule::AirportCode.check(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn amount_is_non_negative_with_at_most_two_decimals() {
+        for text in ["0", "12", "12.5", "12.50"].iter() {
+            assert!(Rule::Amount.check(text).is_ok(), "{}", text);
+        }
+        for text in ["12.505", "-1", "NaN", "inf", "1e5", "12,50", "twelve", "."].iter() {
+            assert!(Rule::Amount.check(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn end_date_may_be_on_but_not_before_the_start_date() {
+        let rule = not_before_text("2025-03-12");
+        assert!(rule.check("2025-03-12").is_ok());
+        assert!(rule.check("2025-03-13").is_ok());
+        assert_eq!(rule.check("2025-03-11"), Err("The end date is before the start date.".to_string()));
+        // Left to the date rule when either date can't be read
+        assert!(rule.check("soon").is_ok());
+        assert!(not_before_text("soon").check("2025-03-11").is_ok());
+    }
+
+    #[test]
+    fn check_all_returns_the_first_failing_rule() {
+        let rules = [Rule::Required, Rule::custom(|_| Err("custom".to_string()))];
+        assert_eq!(check_all(&rules, ""), Err("This field is required.".to_string()));
+        assert_eq!(check_all(&rules, "x"), Err("custom".to_string()));
+    }
+}